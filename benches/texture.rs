@@ -0,0 +1,65 @@
+//! Compares copying the whole windowed terminal to its texture with copying only the rows that
+//! changed, for a frame where a single cell changed and one where nothing did.
+use bevy::prelude::*;
+use bevy_ratatui::windowed::TerminalTexture;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ratatui::{Terminal, text::Text};
+use soft_ratatui::{
+    EmbeddedGraphics, SoftBackend, embedded_graphics_unicodefonts::mono_8x13_atlas,
+};
+
+fn texture_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("texture_update");
+    for (width, height) in [(100, 50), (250, 100), (400, 200)] {
+        let backend =
+            SoftBackend::<EmbeddedGraphics>::new(width, height, mono_8x13_atlas(), None, None);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let line = "The quick brown fox jumps over the lazy dog. ".repeat(width as usize / 8);
+        let text = Text::raw(vec![line.as_str(); height as usize].join("\n"));
+        let size = format!("{width}x{height}");
+        // Redraws the same text with a single cell alternating between two symbols.
+        let mut frames = 0u32;
+        let mut draw_one_cell = |terminal: &mut Terminal<SoftBackend<EmbeddedGraphics>>| {
+            frames += 1;
+            let symbol = if frames.is_multiple_of(2) { "x" } else { "y" };
+            terminal
+                .draw(|frame| {
+                    frame.render_widget(&text, frame.area());
+                    frame.buffer_mut()[(0, 0)].set_symbol(symbol);
+                })
+                .unwrap();
+        };
+        draw_one_cell(&mut terminal);
+        let mut texture = TerminalTexture::default();
+        let mut image = Image::default();
+        texture.copy(terminal.backend(), &mut image, &[]);
+
+        group.bench_function(BenchmarkId::new("full_copy", &size), |b| {
+            b.iter(|| {
+                draw_one_cell(&mut terminal);
+                texture.invalidate();
+                let rows = texture.dirty_rows(terminal.backend(), &image);
+                texture.copy(terminal.backend(), &mut image, &rows);
+            });
+        });
+        group.bench_function(BenchmarkId::new("dirty_rows", &size), |b| {
+            b.iter(|| {
+                draw_one_cell(&mut terminal);
+                let rows = texture.dirty_rows(terminal.backend(), &image);
+                texture.copy(terminal.backend(), &mut image, &rows);
+            });
+        });
+        group.bench_function(BenchmarkId::new("unchanged", &size), |b| {
+            b.iter(|| {
+                let rows = texture.dirty_rows(terminal.backend(), &image);
+                if !rows.is_empty() {
+                    texture.copy(terminal.backend(), &mut image, &rows);
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, texture_update);
+criterion_main!(benches);
@@ -16,11 +16,8 @@ use std::time::Duration;
 use bevy::{app::AppExit, diagnostic::FrameCount, prelude::*};
 #[cfg(not(feature = "windowed"))]
 use bevy::{app::ScheduleRunnerPlugin, state::app::StatesPlugin};
-#[cfg(not(feature = "windowed"))]
-use bevy_ratatui::event::KeyMessage;
-use bevy_ratatui::{RatatuiContext, RatatuiPlugins};
-#[cfg(not(feature = "windowed"))]
-use ratatui::crossterm::event::KeyEventKind;
+use bevy_ratatui::{RatatuiContext, RatatuiPlugins, event::KeyMessage};
+use ratatui::crossterm::event::{KeyCode, KeyEventKind};
 use ratatui::widgets::{FrameExt, Widget};
 use ratatui::{
     buffer::Buffer,
@@ -42,20 +39,17 @@ fn main() -> Result<()> {
         ))),
         StatesPlugin,
         RatatuiPlugins::default(),
-    ))
-    .add_systems(PreUpdate, keyboard_input_system);
+    ));
 
     #[cfg(feature = "windowed")]
     app.add_plugins((
         DefaultPlugins.set(ImagePlugin::default_nearest()),
-        RatatuiPlugins {
-            enable_input_forwarding: true,
-            ..default()
-        },
-    ))
-    .add_systems(PreUpdate, keyboard_input_system_windowed);
+        RatatuiPlugins::default(),
+    ));
 
-    app.init_resource::<BackgroundColor>()
+    // Both backends send the same input messages, so there is a single input system.
+    app.add_systems(PreUpdate, keyboard_input_system)
+        .init_resource::<BackgroundColor>()
         .init_resource::<Counter>()
         .init_state::<AppState>()
         .add_message::<CounterMessage>()
@@ -89,13 +83,11 @@ fn ui_system(
     Ok(())
 }
 
-#[cfg(not(feature = "windowed"))]
 fn keyboard_input_system(
     mut key_messages: MessageReader<KeyMessage>,
     mut app_exit: MessageWriter<AppExit>,
     mut counter_messages: MessageWriter<CounterMessage>,
 ) {
-    use ratatui::crossterm::event::KeyCode;
     for message in key_messages.read() {
         if let KeyEventKind::Release = message.kind {
             continue;
@@ -119,26 +111,6 @@ fn keyboard_input_system(
     }
 }
 
-#[cfg(feature = "windowed")]
-fn keyboard_input_system_windowed(
-    keys: Res<ButtonInput<KeyCode>>,
-    mut app_exit: MessageWriter<AppExit>,
-    mut counter_messages: MessageWriter<CounterMessage>,
-) {
-    if keys.just_pressed(KeyCode::KeyQ) {
-        app_exit.write_default();
-    }
-    if keys.just_pressed(KeyCode::KeyP) {
-        panic!("Panic!");
-    }
-    if keys.pressed(KeyCode::ArrowLeft) {
-        counter_messages.write(CounterMessage::Decrement);
-    }
-    if keys.pressed(KeyCode::ArrowRight) {
-        counter_messages.write(CounterMessage::Increment);
-    }
-}
-
 #[derive(Default, Resource, Debug, Deref, DerefMut)]
 struct Counter(i32);
 
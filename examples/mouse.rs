@@ -135,7 +135,7 @@ fn mouse_input_system(
     for message in messages.read() {
         let ratatui::crossterm::event::MouseEvent {
             kind, column, row, ..
-        } = message.0;
+        } = message.event;
         let size = context.size().unwrap(); // TODO: handle error properly
         let column = column as f32 / size.width as f32;
         let row = row as f32 / size.height as f32;
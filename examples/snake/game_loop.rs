@@ -3,7 +3,7 @@
 //! The domain rules live in [`crate::game`]. These ordinary Bevy systems show how a
 //! `bevy_ratatui` application can connect terminal conditions to domain resources without putting
 //! behavior in the draw closure. [`apply_game_commands`] consumes translated input,
-//! [`sync_playfield`] derives state from [`bevy_ratatui::RatatuiContext`], and
+//! [`sync_playfield`] derives state from [`bevy_ratatui::event::TerminalSize`], and
 //! [`advance_game`] updates the model from Bevy time.
 //!
 //! The chained order in `main.rs` is meaningful: resize policy runs before commands, commands run
@@ -23,6 +23,7 @@
 use std::time::Duration;
 
 use bevy::{app::AppExit, prelude::*};
+use bevy_ratatui::event::TerminalSize;
 
 use crate::{
     controls::GameCommand,
@@ -168,20 +169,23 @@ pub fn apply_game_commands(
 ///
 /// Resizing restarts the current round because snake segments and food are board coordinates. The
 /// high score remains on the [`Game`] resource so resizing does not erase the session record.
-/// Invalid or temporarily unavailable terminal sizes are recorded as unavailable instead of
-/// changing [`Game::status`](crate::game::Game::status), allowing an explicit player pause to
-/// remain distinct from an environmental suspension.
+/// Undersized terminals are recorded as unavailable instead of changing
+/// [`Game::status`](crate::game::Game::status), allowing an explicit player pause to remain
+/// distinct from an environmental suspension.
 ///
-/// `RatatuiContext::size` can fail while terminal access is unavailable, so the system converts
-/// both an error and an undersized terminal into `None`. Keeping that case in resource state lets
-/// later systems use a normal Bevy guard instead of attempting terminal I/O themselves.
+/// [`TerminalSize`] is kept up to date by `bevy_ratatui`, and a burst of resizes while the window
+/// is dragged arrives as a single change. The system therefore only does work when the size
+/// actually changed, and never performs terminal I/O itself.
 pub fn sync_playfield(
-    context: Res<bevy_ratatui::RatatuiContext>,
+    size: Res<TerminalSize>,
     mut playfield: ResMut<Playfield>,
     mut game: ResMut<Game>,
     mut timing: ResMut<GameTiming>,
 ) {
-    let board = playfield.update_for_size(context.size().ok());
+    if !size.is_changed() {
+        return;
+    }
+    let board = playfield.update_for_size(Some(**size));
 
     if board.is_some_and(|board| game.resize_board(board)) {
         timing.reset(game.step_duration());
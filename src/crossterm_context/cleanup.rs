@@ -5,7 +5,7 @@ use crate::RatatuiContext;
 use super::kitty::KittyEnabled;
 
 #[cfg(feature = "mouse")]
-use super::mouse::MouseEnabled;
+use super::mouse::{MouseEnabled, MouseReporting};
 
 /// Plugin responsible for cleaning up resources in the correct order when exiting.
 ///
@@ -24,6 +24,8 @@ fn cleanup(mut exit: MessageReader<AppExit>, mut commands: Commands) {
         commands.remove_resource::<KittyEnabled>();
         #[cfg(feature = "mouse")]
         commands.remove_resource::<MouseEnabled>();
+        #[cfg(feature = "mouse")]
+        commands.remove_resource::<MouseReporting>();
         commands.remove_resource::<RatatuiContext>();
     }
 }
@@ -0,0 +1,33 @@
+//! Copying to the clipboard through the terminal.
+use std::io::stdout;
+
+use bevy::prelude::*;
+use ratatui::crossterm::{ExecutableCommand, clipboard::CopyToClipboard};
+
+use crate::input::{CopyMessage, InputMessagePlugin};
+
+/// Plugin that asks the terminal to put the text of [`CopyMessage`]s on the system clipboard.
+///
+/// Uses the OSC 52 escape sequence, which terminals may ignore or only allow after asking the user.
+/// Pasting needs no plugin: terminals send pasted text as a
+/// [`PasteMessage`][crate::event::PasteMessage].
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<InputMessagePlugin>() {
+            app.add_plugins(InputMessagePlugin);
+        }
+        app.add_systems(PostUpdate, copy_to_clipboard);
+    }
+}
+
+fn copy_to_clipboard(mut copies: MessageReader<CopyMessage>) {
+    // Only the last copy would end up on the clipboard.
+    let Some(CopyMessage(text)) = copies.read().last() else {
+        return;
+    };
+    if let Err(error) = stdout().execute(CopyToClipboard::to_clipboard_from(text)) {
+        tracing::warn!("Failed to copy to the clipboard: {error}");
+    }
+}
@@ -5,6 +5,7 @@ use bevy::prelude::*;
 use ratatui::Terminal;
 use ratatui::crossterm::{
     ExecutableCommand, cursor,
+    event::{DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange},
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 
@@ -12,12 +13,17 @@ use ratatui::backend::CrosstermBackend;
 
 use crate::{RatatuiPlugins, context::TerminalContext};
 
-use super::{cleanup::CleanupPlugin, error::ErrorPlugin, event::EventPlugin, kitty::KittyPlugin};
+use super::{
+    cleanup::CleanupPlugin, clipboard::ClipboardPlugin, error::ErrorPlugin, event::EventPlugin,
+    kitty::KittyPlugin,
+};
 
 #[cfg(feature = "mouse")]
 use super::mouse::MousePlugin;
-#[cfg(feature = "keyboard")]
+#[cfg(any(feature = "keyboard", feature = "mouse"))]
 use super::translation::TranslationPlugin;
+#[cfg(feature = "bevy_window")]
+use super::window::TerminalWindowPlugin;
 
 /// Ratatui context that will draw to the terminal buffer using crossterm.
 #[derive(Deref, DerefMut, Debug)]
@@ -26,7 +32,10 @@ pub struct CrosstermContext(Terminal<CrosstermBackend<Stdout>>);
 impl TerminalContext<CrosstermBackend<Stdout>> for CrosstermContext {
     fn init() -> Result<Self> {
         let mut stdout = stdout();
-        stdout.execute(EnterAlternateScreen)?;
+        stdout
+            .execute(EnterAlternateScreen)?
+            .execute(EnableFocusChange)?
+            .execute(EnableBracketedPaste)?;
         enable_raw_mode()?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
@@ -36,6 +45,8 @@ impl TerminalContext<CrosstermBackend<Stdout>> for CrosstermContext {
     fn restore() -> Result<()> {
         let mut stdout = stdout();
         stdout
+            .execute(DisableBracketedPaste)?
+            .execute(DisableFocusChange)?
             .execute(LeaveAlternateScreen)?
             .execute(cursor::Show)?;
         disable_raw_mode()?;
@@ -48,13 +59,16 @@ impl TerminalContext<CrosstermBackend<Stdout>> for CrosstermContext {
     ) -> bevy::app::PluginGroupBuilder {
         builder = builder
             .add(CleanupPlugin)
+            .add(ClipboardPlugin)
             .add(ErrorPlugin)
             .add(EventPlugin::default())
             .add(KittyPlugin);
 
         #[cfg(feature = "mouse")]
-        let builder = builder.add(MousePlugin);
-        #[cfg(feature = "keyboard")]
+        let builder = builder.add(MousePlugin::default());
+        #[cfg(feature = "bevy_window")]
+        let builder = builder.add(TerminalWindowPlugin);
+        #[cfg(any(feature = "keyboard", feature = "mouse"))]
         let builder = builder.add(TranslationPlugin);
 
         let mut builder = builder;
@@ -67,7 +81,7 @@ impl TerminalContext<CrosstermBackend<Stdout>> for CrosstermContext {
             builder = builder.disable::<MousePlugin>();
         }
 
-        #[cfg(feature = "keyboard")]
+        #[cfg(any(feature = "keyboard", feature = "mouse"))]
         if !group.enable_input_forwarding {
             builder = builder.disable::<TranslationPlugin>();
         }
@@ -1,7 +1,7 @@
 //! Event handling.
 //!
-//! This module provides a plugin for handling events, and a wrapper around
-//! `crossterm::event::KeyEvent`.
+//! This module provides a plugin that reads events from crossterm and sends them as the input
+//! messages shared with the other contexts, such as [`KeyMessage`].
 //!
 //! # Example
 //!
@@ -27,6 +27,19 @@ use bevy::{app::AppExit, prelude::*};
 use ratatui::crossterm::event::{self, Event::Key, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::layout::Size;
 
+use crate::{
+    RatatuiContext,
+    input::{
+        FocusMessage, InputMessagePlugin, InputSet, KeyMessage, MouseMessage, PasteMessage,
+        ResizeMessage, update_terminal_size,
+    },
+};
+
+#[cfg(feature = "mouse")]
+use super::mouse::{MouseReporting, pixel_to_cell};
+#[cfg(feature = "mouse")]
+use ratatui::crossterm::terminal;
+
 /// A plugin for handling events.
 ///
 /// This plugin reads events from the terminal environment and forwards them as Bevy messages using the
@@ -46,18 +59,21 @@ impl Default for EventPlugin {
 
 impl Plugin for EventPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_message::<KeyMessage>()
-            .add_message::<MouseMessage>()
-            .add_message::<FocusMessage>()
-            .add_message::<ResizeMessage>()
-            .add_message::<PasteMessage>()
-            .add_message::<CrosstermMessage>();
-
-        configure_input_sets(app);
-        app.add_systems(
-            PreUpdate,
-            crossterm_event_system.in_set(InputSet::EmitCrossterm),
-        );
+        if !app.is_plugin_added::<InputMessagePlugin>() {
+            app.add_plugins(InputMessagePlugin);
+        }
+        app.add_message::<CrosstermMessage>()
+            .init_resource::<ResizePolicy>()
+            .add_systems(
+                PreUpdate,
+                (
+                    crossterm_event_system,
+                    clear_after_resize.run_if(|policy: Res<ResizePolicy>| policy.clear),
+                )
+                    .chain()
+                    .before(update_terminal_size)
+                    .in_set(InputSet::EmitCrossterm),
+            );
 
         if self.control_c_interrupt {
             app.add_systems(PreUpdate, control_c_interrupt_system.in_set(InputSet::Post));
@@ -65,75 +81,37 @@ impl Plugin for EventPlugin {
     }
 }
 
-/// Orders the public input extension points in the schedule that emits terminal messages.
-fn configure_input_sets(app: &mut App) {
-    app.configure_sets(
-        PreUpdate,
-        (
-            InputSet::Pre,
-            InputSet::EmitCrossterm,
-            InputSet::CheckEmulation,
-            InputSet::EmitBevy,
-            InputSet::Post,
-        )
-            .chain(),
-    );
-}
-
-/// Input-processing phases ordered within [`PreUpdate`].
-///
-/// Bevy configures system-set ordering separately for each schedule. Systems using these extension
-/// points must therefore be added to `PreUpdate` for the ordering below to apply:
-///
-/// ```
-/// # use bevy::prelude::*;
-/// # use bevy_ratatui::event::{InputSet, KeyMessage};
-/// # fn handle_keys(_: MessageReader<KeyMessage>) {}
-/// # let mut app = App::new();
-/// app.add_systems(PreUpdate, handle_keys.in_set(InputSet::Post));
-/// ```
-#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
-pub enum InputSet {
-    /// Run before any input messages are emitted.
-    Pre,
-    /// Emit the crossterm messages.
-    EmitCrossterm,
-    /// Check for emulation.
-    CheckEmulation,
-    /// Emit the bevy messages if [`crate::translation::TranslationPlugin`] has been added.
-    EmitBevy,
-    /// Run after all input messages are emitted.
-    Post,
-}
-
 /// A message that is sent whenever an event is read from crossterm.
 #[derive(Message, Deref, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct CrosstermMessage(pub event::Event);
 
-/// A message that is sent whenever a key event is read from crossterm.
-#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub struct KeyMessage(pub event::KeyEvent);
-
-/// A message that is sent whenever a mouse event is read from crossterm.
-#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub struct MouseMessage(pub event::MouseEvent);
-
-/// A message that is sent when the terminal gains or loses focus.
-#[derive(Message, Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub enum FocusMessage {
-    Gained,
-    Lost,
+/// Controls how terminal resizes are reported and handled.
+///
+/// Dragging a window edge can make the terminal report dozens of sizes in a single frame.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ResizePolicy {
+    /// Only send a [`ResizeMessage`] for the last resize read in a frame.
+    ///
+    /// [`CrosstermMessage`]s are still sent for every resize event.
+    pub coalesce: bool,
+    /// Clear the terminal after a resize, so the next draw repaints every cell.
+    ///
+    /// Some terminals reflow or keep stale content when resized, which ratatui's diffing then
+    /// leaves on screen. Clearing avoids those artifacts at the cost of a full redraw.
+    pub clear: bool,
 }
 
-/// An event that is sent when the terminal is resized.
-#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub struct ResizeMessage(pub Size);
-
-/// An event that is sent when text is pasted into the terminal.
-#[derive(Message, Deref, Clone, PartialEq, Eq, Hash, Debug)]
-pub struct PasteMessage(pub String);
+impl Default for ResizePolicy {
+    fn default() -> Self {
+        Self {
+            coalesce: true,
+            clear: false,
+        }
+    }
+}
 
 /// System that reads events from crossterm and forwards them as Bevy messages.
+#[allow(clippy::too_many_arguments)]
 pub fn crossterm_event_system(
     mut messages: MessageWriter<CrosstermMessage>,
     mut keys: MessageWriter<KeyMessage>,
@@ -141,7 +119,16 @@ pub fn crossterm_event_system(
     mut focus: MessageWriter<FocusMessage>,
     mut paste: MessageWriter<PasteMessage>,
     mut resize: MessageWriter<ResizeMessage>,
+    policy: Res<ResizePolicy>,
+    #[cfg(feature = "mouse")] mouse_reporting: Option<Res<MouseReporting>>,
 ) -> Result {
+    let mut last_resize = None;
+    #[cfg(feature = "mouse")]
+    let pixel_coordinates = mouse_reporting.is_some_and(|mouse| mouse.pixel_coordinates);
+    // Only queried once a pixel report arrives, and at most once per frame.
+    #[cfg(feature = "mouse")]
+    let mut window_size = None;
+
     while event::poll(Duration::ZERO)? {
         let event = event::read()?;
         match event {
@@ -154,21 +141,62 @@ pub fn crossterm_event_system(
             event::Event::FocusGained => {
                 focus.write(FocusMessage::Gained);
             }
+            #[cfg(feature = "mouse")]
+            event::Event::Mouse(event) if pixel_coordinates => {
+                let size = match window_size {
+                    Some(ref size) => size,
+                    None => window_size.insert(terminal::window_size()?),
+                };
+                mouse.write(pixel_mouse_message(event, size));
+            }
             event::Event::Mouse(event) => {
-                mouse.write(MouseMessage(event));
+                mouse.write(MouseMessage::new(event));
             }
             event::Event::Paste(ref s) => {
                 paste.write(PasteMessage(s.clone()));
             }
+            event::Event::Resize(columns, rows) if policy.coalesce => {
+                last_resize = Some(Size::new(columns, rows));
+            }
             event::Event::Resize(columns, rows) => {
                 resize.write(ResizeMessage(Size::new(columns, rows)));
             }
         }
         messages.write(CrosstermMessage(event));
     }
+    if let Some(size) = last_resize {
+        resize.write(ResizeMessage(size));
+    }
+    Ok(())
+}
+
+/// Clears the terminal after a resize when [`ResizePolicy::clear`] is set.
+fn clear_after_resize(
+    mut resize: MessageReader<ResizeMessage>,
+    mut context: ResMut<RatatuiContext>,
+) -> Result {
+    if resize.read().last().is_some() {
+        // Resize the buffers first so the clear covers the whole new area.
+        context.autoresize()?;
+        context.clear()?;
+    }
     Ok(())
 }
 
+/// Builds a [`MouseMessage`] from an event whose position was reported in pixels.
+#[cfg(feature = "mouse")]
+fn pixel_mouse_message(mut event: event::MouseEvent, size: &terminal::WindowSize) -> MouseMessage {
+    let pixel_position = UVec2::new(event.column.into(), event.row.into());
+    if let Some((column, row)) = pixel_to_cell(event.column, event.row, size) {
+        event.column = column;
+        event.row = row;
+    }
+    MouseMessage {
+        event,
+        pixel_position: Some(pixel_position),
+    }
+}
+
 /// System that sends an `AppExit` message when `Ctrl+C` is pressed.
 fn control_c_interrupt_system(
     mut key_messages: MessageReader<KeyMessage>,
@@ -183,57 +211,3 @@ fn control_c_interrupt_system(
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    /// Execution trace used to verify the schedule contract exposed by [`InputSet`].
-    #[derive(Default, Resource)]
-    struct InputOrder(Vec<InputSet>);
-
-    #[test]
-    fn input_sets_run_in_order_during_pre_update() {
-        let mut app = App::new();
-        configure_input_sets(&mut app);
-        app.init_resource::<InputOrder>()
-            .add_systems(PreUpdate, record_pre.in_set(InputSet::Pre))
-            .add_systems(PreUpdate, record_crossterm.in_set(InputSet::EmitCrossterm))
-            .add_systems(PreUpdate, record_emulation.in_set(InputSet::CheckEmulation))
-            .add_systems(PreUpdate, record_bevy.in_set(InputSet::EmitBevy))
-            .add_systems(PreUpdate, record_post.in_set(InputSet::Post));
-
-        app.update();
-
-        assert_eq!(
-            app.world().resource::<InputOrder>().0,
-            [
-                InputSet::Pre,
-                InputSet::EmitCrossterm,
-                InputSet::CheckEmulation,
-                InputSet::EmitBevy,
-                InputSet::Post,
-            ]
-        );
-    }
-
-    fn record_pre(mut order: ResMut<InputOrder>) {
-        order.0.push(InputSet::Pre);
-    }
-
-    fn record_crossterm(mut order: ResMut<InputOrder>) {
-        order.0.push(InputSet::EmitCrossterm);
-    }
-
-    fn record_emulation(mut order: ResMut<InputOrder>) {
-        order.0.push(InputSet::CheckEmulation);
-    }
-
-    fn record_bevy(mut order: ResMut<InputOrder>) {
-        order.0.push(InputSet::EmitBevy);
-    }
-
-    fn record_post(mut order: ResMut<InputOrder>) {
-        order.0.push(InputSet::Post);
-    }
-}
@@ -1,10 +1,13 @@
 pub mod cleanup;
+pub mod clipboard;
 pub mod context;
 pub mod error;
 pub mod event;
 pub mod kitty;
 #[cfg(feature = "mouse")]
 pub mod mouse;
+#[cfg(feature = "bevy_window")]
+pub mod window;
 
-#[cfg(feature = "keyboard")]
+#[cfg(any(feature = "keyboard", feature = "mouse"))]
 pub mod translation;
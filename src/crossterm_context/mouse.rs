@@ -1,33 +1,230 @@
 //! Mouse support.
-use std::io::stdout;
+//!
+//! By default [`MousePlugin`] asks the terminal for every mouse event, including motion while no
+//! button is held. Apps that only need clicks can ask for less so the terminal does not flood the
+//! input queue with motion reports:
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_ratatui::RatatuiPlugins;
+//! # use bevy_ratatui::mouse::{MousePlugin, MouseTracking};
+//! App::new().add_plugins(
+//!     RatatuiPlugins {
+//!         enable_mouse_capture: true,
+//!         ..default()
+//!     }
+//!     .set(MousePlugin {
+//!         tracking: MouseTracking::Click,
+//!         ..default()
+//!     }),
+//! );
+//! ```
+use std::{fmt, io::stdout};
 
 use bevy::prelude::*;
 use ratatui::crossterm::{
-    ExecutableCommand,
+    Command, ExecutableCommand,
     event::{DisableMouseCapture, EnableMouseCapture},
+    terminal::{WindowSize, window_size},
 };
 
 /// Plugin responsible for enabling mouse capture.
-pub struct MousePlugin;
+#[derive(Default)]
+pub struct MousePlugin {
+    /// Which mouse activity the terminal should report.
+    pub tracking: MouseTracking,
+    /// Ask the terminal for pixel rather than cell coordinates (SGR-pixel mode, 1016).
+    ///
+    /// Pixel reports are converted back to cells before being sent as a
+    /// [`MouseMessage`][crate::event::MouseMessage], with the original position kept in
+    /// [`MouseMessage::pixel_position`][crate::event::MouseMessage::pixel_position]. Terminals that
+    /// do not report their size in pixels fall back to cell coordinates.
+    pub pixel_coordinates: bool,
+}
 
 impl Plugin for MousePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, mouse_setup);
+        let tracking = self.tracking;
+        let pixel_coordinates = self.pixel_coordinates;
+        app.add_systems(Startup, move |commands: Commands| {
+            mouse_setup(commands, tracking, pixel_coordinates)
+        });
     }
 }
 
+/// How much mouse activity the terminal reports.
+///
+/// Every mode reports button presses, releases and the scroll wheel. The modes differ in which
+/// motion is reported as well.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseTracking {
+    /// Only report presses, releases and scrolling (mode 1000).
+    Click,
+    /// Also report motion while a button is held, i.e. drags (mode 1002).
+    ButtonMotion,
+    /// Report all motion, whether or not a button is held (mode 1003).
+    ///
+    /// This matches crossterm's `EnableMouseCapture`.
+    #[default]
+    AnyMotion,
+}
+
 /// Resource indicating that mouse capture was successfully enabled in the current terminal buffer.
 #[derive(Resource, Default)]
 pub struct MouseEnabled;
 
-fn mouse_setup(mut commands: Commands) -> Result {
-    stdout().execute(EnableMouseCapture)?;
+/// Resource describing what the terminal was asked to report, inserted along with
+/// [`MouseEnabled`].
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseReporting {
+    /// The tracking mode that was requested from the terminal.
+    pub tracking: MouseTracking,
+    /// Whether the terminal was asked to report pixel coordinates.
+    pub pixel_coordinates: bool,
+}
+
+fn mouse_setup(mut commands: Commands, tracking: MouseTracking, pixel_coordinates: bool) -> Result {
+    // Without a pixel size there is no way to map pixel reports back to cells.
+    let pixel_coordinates =
+        pixel_coordinates && window_size().is_ok_and(|size| has_pixel_size(&size));
+    stdout().execute(EnableMouseTracking {
+        tracking,
+        pixel_coordinates,
+    })?;
     commands.insert_resource(MouseEnabled);
+    commands.insert_resource(MouseReporting {
+        tracking,
+        pixel_coordinates,
+    });
     Ok(())
 }
 
 impl Drop for MouseEnabled {
     fn drop(&mut self) {
+        // Terminals ignore turning off a mode that was never turned on.
+        let _ = stdout().execute(DisableSgrPixels);
         let _ = stdout().execute(DisableMouseCapture);
     }
 }
+
+/// Converts a mouse position reported in pixels to the cell that contains it.
+///
+/// Returns `None` if the terminal did not report its size in pixels.
+pub(crate) fn pixel_to_cell(x: u16, y: u16, size: &WindowSize) -> Option<(u16, u16)> {
+    if !has_pixel_size(size) {
+        return None;
+    }
+    let column = u32::from(x) * u32::from(size.columns) / u32::from(size.width);
+    let row = u32::from(y) * u32::from(size.rows) / u32::from(size.height);
+    Some((
+        (column as u16).min(size.columns.saturating_sub(1)),
+        (row as u16).min(size.rows.saturating_sub(1)),
+    ))
+}
+
+fn has_pixel_size(size: &WindowSize) -> bool {
+    size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0
+}
+
+/// Enables mouse reporting in a [`MouseTracking`] mode, always using SGR encoding.
+struct EnableMouseTracking {
+    tracking: MouseTracking,
+    pixel_coordinates: bool,
+}
+
+impl Command for EnableMouseTracking {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if self.tracking == MouseTracking::AnyMotion && !self.pixel_coordinates {
+            return EnableMouseCapture.write_ansi(f);
+        }
+        f.write_str("\x1b[?1000h")?;
+        match self.tracking {
+            MouseTracking::Click => {}
+            MouseTracking::ButtonMotion => f.write_str("\x1b[?1002h")?,
+            MouseTracking::AnyMotion => f.write_str("\x1b[?1002h\x1b[?1003h")?,
+        }
+        f.write_str("\x1b[?1006h")?;
+        if self.pixel_coordinates {
+            f.write_str("\x1b[?1016h")?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        // The Windows console has no tracking modes; it reports everything.
+        EnableMouseCapture.execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        EnableMouseCapture.is_ansi_code_supported()
+    }
+}
+
+/// Turns SGR-pixel reporting back off. `DisableMouseCapture` does not know about it.
+struct DisableSgrPixels;
+
+impl Command for DisableSgrPixels {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1b[?1016l")
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(command: impl Command) -> String {
+        let mut out = String::new();
+        command.write_ansi(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn click_tracking_does_not_request_motion() {
+        let ansi = write(EnableMouseTracking {
+            tracking: MouseTracking::Click,
+            pixel_coordinates: false,
+        });
+        assert_eq!(ansi, "\x1b[?1000h\x1b[?1006h");
+    }
+
+    #[test]
+    fn pixel_tracking_requests_sgr_pixels() {
+        let ansi = write(EnableMouseTracking {
+            tracking: MouseTracking::ButtonMotion,
+            pixel_coordinates: true,
+        });
+        assert_eq!(ansi, "\x1b[?1000h\x1b[?1002h\x1b[?1006h\x1b[?1016h");
+    }
+
+    #[test]
+    fn pixels_map_to_the_containing_cell() {
+        let size = WindowSize {
+            rows: 24,
+            columns: 80,
+            width: 800,
+            height: 480,
+        };
+        assert_eq!(pixel_to_cell(0, 0, &size), Some((0, 0)));
+        assert_eq!(pixel_to_cell(19, 39, &size), Some((1, 1)));
+        assert_eq!(pixel_to_cell(800, 480, &size), Some((79, 23)));
+    }
+
+    #[test]
+    fn unknown_pixel_size_cannot_be_mapped() {
+        let size = WindowSize {
+            rows: 24,
+            columns: 80,
+            width: 0,
+            height: 0,
+        };
+        assert_eq!(pixel_to_cell(10, 10, &size), None);
+    }
+}
@@ -2,21 +2,22 @@
 
 use std::{collections::HashSet, hash::Hash, time::Duration};
 
-use crate::crossterm_context::event::{InputSet, KeyMessage};
+use crate::{
+    crossterm_context::window::TerminalWindow,
+    input::{InputSet, KeyMessage},
+};
 use bevy::{
     input::{ButtonState, keyboard::KeyboardInput},
     prelude::*,
 };
 use ratatui::crossterm::event::KeyModifiers;
 
-pub struct TranslationPlugin;
+/// Forwards [`KeyMessage`]s as [`KeyboardInput`] messages. Added by
+/// [`TranslationPlugin`][super::TranslationPlugin].
+pub(super) struct KeyboardTranslationPlugin;
 
-impl Plugin for TranslationPlugin {
+impl Plugin for KeyboardTranslationPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        if !app.is_plugin_added::<bevy::input::InputPlugin>() {
-            // We need this plugin to submit our messages.
-            app.add_plugins(bevy::input::InputPlugin);
-        }
         if !app.is_plugin_added::<bevy::time::TimePlugin>() {
             // We need this plugin for the delay timer.
             app.add_plugins(bevy::time::TimePlugin);
@@ -25,7 +26,6 @@ impl Plugin for TranslationPlugin {
             .init_resource::<Detected>()
             .init_resource::<EmulationPolicy>()
             .init_resource::<Emulate>()
-            .add_systems(Startup, setup_window)
             .add_systems(
                 PreUpdate,
                 reset_emulation_check
@@ -298,7 +298,7 @@ fn reset_emulation_check(mut commands: Commands) {
 #[allow(clippy::too_many_arguments)]
 fn send_key_messages_with_emulation(
     mut keys: MessageReader<KeyMessage>,
-    bevy_window: Single<Entity, With<DummyWindow>>,
+    bevy_window: Single<Entity, With<TerminalWindow>>,
     mut modifiers: Local<Modifiers>,
     mut delayed_modifiers: Local<Modifiers>,
     mut last_pressed: Local<LastPress>,
@@ -397,7 +397,7 @@ fn send_key_messages_with_emulation(
 /// when emulation is not involved.
 fn send_key_messages_no_emulation(
     mut keys: MessageReader<KeyMessage>,
-    bevy_window: Single<Entity, With<DummyWindow>>,
+    bevy_window: Single<Entity, With<TerminalWindow>>,
     mut keyboard_input: MessageWriter<KeyboardInput>,
     mut key_repeat_queue: Local<Vec<KeyboardInput>>,
 ) {
@@ -420,15 +420,6 @@ fn send_key_messages_no_emulation(
     }
 }
 
-#[derive(Debug, Component)]
-struct DummyWindow;
-
-/// This is a dummy window to satisfy the [KeyboardInput] struct.
-fn setup_window(mut commands: Commands) {
-    // Insert our window entity so that other parts of our app can use them.
-    commands.spawn(DummyWindow);
-}
-
 fn modifier_to_bevy(
     modifier: bevy::input::keyboard::Key,
     state: bevy::input::ButtonState,
@@ -1,4 +1,4 @@
-//! Forwards terminal key events to the bevy input system.
+//! Forwards terminal key and mouse events to the bevy input system.
 //!
 //! With this plugin one can use the standard bevy input system like
 //! [`ButtonInput`][bevy::input::ButtonInput] with bevy_ratatui:
@@ -8,6 +8,17 @@
 //! - and `MessageReader<`[`KeyboardInput`][bevy::input::keyboard::KeyboardInput]`>` for its
 //!   lowest-level events.
 //!
+//! With the `mouse` feature, mouse capture enabled, and this plugin present, mouse events are
+//! forwarded as well:
+//!
+//! - `ButtonInput`[`<MouseButton>`][bevy::input::mouse::MouseButton] for buttons,
+//! - `MessageReader<`[`MouseButtonInput`][bevy::input::mouse::MouseButtonInput]`>` and
+//!   `MessageReader<`[`MouseWheel`][bevy::input::mouse::MouseWheel]`>`,
+//! - and `MessageReader<`[`CursorMoved`][bevy::window::CursorMoved]`>`, in cells by default. See
+//!   [`CursorCoordinates`][crate::terminal_window::CursorCoordinates] to report pixels instead.
+//!
+//! Input messages are reported against the [terminal window][crate::terminal_window].
+//!
 //! The crossterm events are still present and usable with this plugin present.
 //!
 //! # Usage
@@ -104,5 +115,36 @@
 //! here](https://sw.kovidgoyal.net/kitty/keyboard-protocol/) for a list of
 //! terminals implementing this protocol.
 
+use bevy::prelude::*;
+
+use super::window::TerminalWindowPlugin;
+
+#[cfg(feature = "keyboard")]
 mod keyboard_translation;
+#[cfg(feature = "mouse")]
+mod mouse_translation;
+
+#[cfg(feature = "keyboard")]
 pub use keyboard_translation::*;
+
+/// Plugin that forwards terminal input to the bevy input system. See the [module
+/// documentation][self] for details.
+pub struct TranslationPlugin;
+
+impl Plugin for TranslationPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        if !app.is_plugin_added::<bevy::input::InputPlugin>() {
+            // We need this plugin to submit our messages.
+            app.add_plugins(bevy::input::InputPlugin);
+        }
+        if !app.is_plugin_added::<TerminalWindowPlugin>() {
+            // We need the terminal window for the `window` field of bevy's input messages.
+            app.add_plugins(TerminalWindowPlugin);
+        }
+
+        #[cfg(feature = "keyboard")]
+        app.add_plugins(keyboard_translation::KeyboardTranslationPlugin);
+        #[cfg(feature = "mouse")]
+        app.add_plugins(mouse_translation::MouseTranslationPlugin);
+    }
+}
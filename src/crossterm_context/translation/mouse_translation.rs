@@ -0,0 +1,164 @@
+//! Input forwarding for the mouse
+
+use bevy::{
+    input::{
+        ButtonState,
+        mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel},
+        touch::TouchPhase,
+    },
+    prelude::*,
+    window::CursorMoved,
+};
+use ratatui::crossterm::event::MouseEventKind;
+
+use crate::{
+    crossterm_context::window::{CursorCoordinates, TerminalWindow},
+    input::{InputSet, MouseMessage},
+};
+
+/// Forwards [`MouseMessage`]s as bevy mouse and cursor messages. Added by
+/// [`TranslationPlugin`][super::TranslationPlugin].
+pub(super) struct MouseTranslationPlugin;
+
+impl Plugin for MouseTranslationPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        // Normally registered by bevy's `WindowPlugin`, which terminal apps do not use.
+        app.add_message::<CursorMoved>()
+            .add_systems(PreUpdate, send_mouse_messages.in_set(InputSet::EmitBevy));
+    }
+}
+
+fn cursor_position(coordinates: CursorCoordinates, message: &MouseMessage) -> Vec2 {
+    match (coordinates, message.pixel_position) {
+        (CursorCoordinates::Pixels, Some(pixel_position)) => pixel_position.as_vec2(),
+        _ => Vec2::new(message.column as f32, message.row as f32),
+    }
+}
+
+fn send_mouse_messages(
+    mut mouse: MessageReader<MouseMessage>,
+    bevy_window: Single<(Entity, &mut Window), With<TerminalWindow>>,
+    coordinates: Res<CursorCoordinates>,
+    mut last_position: Local<Option<Vec2>>,
+    mut cursor_moved: MessageWriter<CursorMoved>,
+    mut button_input: MessageWriter<MouseButtonInput>,
+    mut mouse_wheel: MessageWriter<MouseWheel>,
+) {
+    let (window, mut bevy_window) = bevy_window.into_inner();
+    for message in mouse.read() {
+        // Every terminal mouse report carries a position, so clicks and scrolls can move the
+        // cursor too, e.g. when only click tracking is enabled.
+        let position = cursor_position(*coordinates, message);
+        if *last_position != Some(position) {
+            bevy_window.set_cursor_position(Some(position));
+            cursor_moved.write(CursorMoved {
+                window,
+                position,
+                delta: last_position.map(|last| position - last),
+            });
+            *last_position = Some(position);
+        }
+
+        let (x, y) = match message.kind {
+            MouseEventKind::Down(button) => {
+                button_input.write(MouseButtonInput {
+                    button: to_bevy_button(button),
+                    state: ButtonState::Pressed,
+                    window,
+                });
+                continue;
+            }
+            MouseEventKind::Up(button) => {
+                button_input.write(MouseButtonInput {
+                    button: to_bevy_button(button),
+                    state: ButtonState::Released,
+                    window,
+                });
+                continue;
+            }
+            MouseEventKind::Drag(_) | MouseEventKind::Moved => continue,
+            // Bevy follows winit: positive values move the content right and down.
+            MouseEventKind::ScrollUp => (0.0, 1.0),
+            MouseEventKind::ScrollDown => (0.0, -1.0),
+            MouseEventKind::ScrollLeft => (1.0, 0.0),
+            MouseEventKind::ScrollRight => (-1.0, 0.0),
+        };
+        mouse_wheel.write(MouseWheel {
+            unit: MouseScrollUnit::Line,
+            x,
+            y,
+            window,
+            phase: TouchPhase::Moved,
+        });
+    }
+}
+
+fn to_bevy_button(button: ratatui::crossterm::event::MouseButton) -> MouseButton {
+    use ratatui::crossterm::event::MouseButton as c;
+    match button {
+        c::Left => MouseButton::Left,
+        c::Right => MouseButton::Right,
+        c::Middle => MouseButton::Middle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+    use ratatui::crossterm::event::{KeyModifiers, MouseButton as CrosstermButton, MouseEvent};
+    use ratatui::layout::Size;
+
+    use super::*;
+
+    fn mouse_message(kind: MouseEventKind, column: u16, row: u16) -> MouseMessage {
+        MouseMessage::new(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn clicks_move_the_cursor_and_press_the_button() {
+        let mut app = App::new();
+        app.add_plugins(bevy::input::InputPlugin)
+            .add_message::<MouseMessage>()
+            .init_resource::<CursorCoordinates>()
+            .add_plugins(MouseTranslationPlugin);
+        let window = app
+            .world_mut()
+            .spawn((
+                Window {
+                    resolution: (80, 24).into(),
+                    ..default()
+                },
+                TerminalWindow {
+                    cells: Size::new(80, 24),
+                    pixels: None,
+                },
+            ))
+            .id();
+
+        app.world_mut().write_message(mouse_message(
+            MouseEventKind::Down(CrosstermButton::Left),
+            3,
+            4,
+        ));
+        app.update();
+
+        let cursor = app.world().resource::<Messages<CursorMoved>>();
+        let moves: Vec<_> = cursor.iter_current_update_messages().collect();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].position, Vec2::new(3.0, 4.0));
+        assert_eq!(moves[0].delta, None);
+        let cursor = app.world().get::<Window>(window).unwrap().cursor_position();
+        assert_eq!(cursor, Some(Vec2::new(3.0, 4.0)));
+
+        let buttons = app.world().resource::<Messages<MouseButtonInput>>();
+        let presses: Vec<_> = buttons.iter_current_update_messages().collect();
+        assert_eq!(presses.len(), 1);
+        assert_eq!(presses[0].button, MouseButton::Left);
+        assert_eq!(presses[0].state, ButtonState::Pressed);
+    }
+}
@@ -0,0 +1,213 @@
+//! The terminal as a bevy [`Window`].
+//!
+//! [`TerminalWindowPlugin`] spawns an entity representing the terminal, with a [`Window`] marked as
+//! the [`PrimaryWindow`] and a [`TerminalWindow`] component. No OS window is opened; the entity
+//! exists so that bevy systems which query windows keep working in the terminal:
+//!
+//! - the window resolution follows the terminal size, in cells unless [`CursorCoordinates`] asks
+//!   for pixels, and a [`WindowResized`] message is sent when it changes,
+//! - `focused` follows [`FocusMessage`]s, sending [`WindowFocused`],
+//! - the cursor position follows the mouse when input forwarding is enabled,
+//! - and changing the window title changes the terminal title.
+use std::io::{self, stdout};
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowFocused, WindowResized},
+};
+use ratatui::crossterm::{
+    ExecutableCommand,
+    terminal::{self, SetTitle},
+};
+use ratatui::layout::Size;
+
+use crate::input::{FocusMessage, InputSet, ResizeMessage};
+use crate::ratatui_plugin::context_setup;
+
+/// Plugin that represents the terminal as a bevy [`Window`] entity. See the [module
+/// documentation][self] for details.
+pub struct TerminalWindowPlugin;
+
+impl Plugin for TerminalWindowPlugin {
+    fn build(&self, app: &mut App) {
+        // Normally registered by bevy's `WindowPlugin`, which terminal apps do not use.
+        app.add_message::<WindowResized>()
+            .add_message::<WindowFocused>()
+            .init_resource::<CursorCoordinates>()
+            .add_systems(Startup, spawn_terminal_window.after(context_setup))
+            .add_systems(
+                PreUpdate,
+                (resize_terminal_window, focus_terminal_window).in_set(InputSet::EmitBevy),
+            )
+            .add_systems(PostUpdate, set_terminal_title);
+    }
+}
+
+/// The terminal that the [`Window`] on the same entity represents.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalWindow {
+    /// The size of the terminal in cells.
+    pub cells: Size,
+    /// The size of the terminal in pixels, if the terminal reports it.
+    pub pixels: Option<UVec2>,
+}
+
+impl TerminalWindow {
+    /// Reads the current size of the terminal.
+    pub fn query() -> io::Result<Self> {
+        match terminal::window_size() {
+            Ok(size) => Ok(Self {
+                cells: Size::new(size.columns, size.rows),
+                pixels: (size.width > 0 && size.height > 0)
+                    .then(|| UVec2::new(size.width.into(), size.height.into())),
+            }),
+            // Not every platform can report the pixel size.
+            Err(_) => {
+                let (columns, rows) = terminal::size()?;
+                Ok(Self {
+                    cells: Size::new(columns, rows),
+                    pixels: None,
+                })
+            }
+        }
+    }
+
+    /// The window resolution for the given units.
+    pub fn resolution(&self, coordinates: CursorCoordinates) -> UVec2 {
+        match (coordinates, self.pixels) {
+            (CursorCoordinates::Pixels, Some(pixels)) => pixels,
+            _ => UVec2::new(self.cells.width.into(), self.cells.height.into()),
+        }
+    }
+}
+
+/// The units of the terminal window's resolution and cursor position.
+///
+/// Bevy reports window sizes and cursor positions in logical pixels. A terminal normally only knows
+/// which cell the pointer is over, so by default the terminal window uses cells: its resolution is
+/// `(columns, rows)` and the cursor is at `(column, row)`.
+#[derive(Debug, Default, Resource, Clone, Copy, PartialEq, Eq)]
+pub enum CursorCoordinates {
+    /// Use the columns and rows of the terminal.
+    #[default]
+    Cells,
+    /// Use pixels.
+    ///
+    /// Requires a terminal that reports its size in pixels and, for the cursor,
+    /// [`MousePlugin::pixel_coordinates`][crate::mouse::MousePlugin::pixel_coordinates]. Falls back
+    /// to cells otherwise.
+    Pixels,
+}
+
+fn spawn_terminal_window(mut commands: Commands, coordinates: Res<CursorCoordinates>) -> Result {
+    let terminal = TerminalWindow::query()?;
+    let resolution = terminal.resolution(*coordinates);
+    commands.spawn((
+        Window {
+            // Left empty so the terminal's own title is kept until the app sets one.
+            title: String::new(),
+            resolution: resolution.into(),
+            focused: true,
+            ..default()
+        },
+        PrimaryWindow,
+        terminal,
+    ));
+    Ok(())
+}
+
+fn resize_terminal_window(
+    mut resize: MessageReader<ResizeMessage>,
+    coordinates: Res<CursorCoordinates>,
+    window: Single<(Entity, &mut Window, &mut TerminalWindow)>,
+    mut window_resized: MessageWriter<WindowResized>,
+) {
+    let (entity, mut window, mut terminal) = window.into_inner();
+    if let Some(size) = resize.read().last() {
+        terminal.cells = **size;
+        // Resize messages only carry cells, so ask the terminal for the pixels.
+        terminal.pixels = TerminalWindow::query().ok().and_then(|size| size.pixels);
+    } else if !coordinates.is_changed() {
+        return;
+    }
+
+    let resolution = terminal.resolution(*coordinates);
+    if window.resolution.physical_size() == resolution {
+        return;
+    }
+    window
+        .resolution
+        .set_physical_resolution(resolution.x, resolution.y);
+    window_resized.write(WindowResized {
+        window: entity,
+        width: window.width(),
+        height: window.height(),
+    });
+}
+
+fn focus_terminal_window(
+    mut focus: MessageReader<FocusMessage>,
+    window: Single<(Entity, &mut Window), With<TerminalWindow>>,
+    mut window_focused: MessageWriter<WindowFocused>,
+) {
+    let (entity, mut window) = window.into_inner();
+    for message in focus.read() {
+        window.focused = *message == FocusMessage::Gained;
+        window_focused.write(WindowFocused {
+            window: entity,
+            focused: window.focused,
+        });
+    }
+}
+
+fn set_terminal_title(
+    window: Single<&Window, (With<TerminalWindow>, Changed<Window>)>,
+    mut title: Local<String>,
+) -> Result {
+    if window.title != *title {
+        stdout().execute(SetTitle(&window.title))?;
+        title.clone_from(&window.title);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+
+    use super::*;
+
+    #[test]
+    fn resize_updates_the_window_resolution_in_cells() {
+        let mut app = App::new();
+        app.add_message::<ResizeMessage>()
+            .add_message::<WindowResized>()
+            .init_resource::<CursorCoordinates>()
+            .add_systems(Update, resize_terminal_window);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Window {
+                    resolution: (80, 24).into(),
+                    ..default()
+                },
+                TerminalWindow {
+                    cells: Size::new(80, 24),
+                    pixels: None,
+                },
+            ))
+            .id();
+        app.update();
+
+        app.world_mut()
+            .write_message(ResizeMessage(Size::new(100, 30)));
+        app.update();
+
+        let window = app.world().get::<Window>(entity).unwrap();
+        assert_eq!(window.resolution.physical_size(), UVec2::new(100, 30));
+        let resized = app.world().resource::<Messages<WindowResized>>();
+        let resized: Vec<_> = resized.iter_current_update_messages().collect();
+        assert_eq!(resized.len(), 1);
+        assert_eq!((resized[0].width, resized[0].height), (100.0, 30.0));
+    }
+}
@@ -0,0 +1,235 @@
+//! Input messages.
+//!
+//! This module provides the messages that both the crossterm and the windowed contexts send their
+//! input as, so one system can handle input whichever context the app is built with. The key and
+//! mouse events use crossterm's types, which are plain data and also available when the crossterm
+//! context is not.
+use bevy::prelude::*;
+use ratatui::crossterm::event;
+use ratatui::layout::Size;
+
+use crate::{RatatuiContext, ratatui_plugin::context_setup};
+
+/// Plugin that registers the input messages and [`TerminalSize`]. Added by the plugins that read
+/// input from a backend, such as [`EventPlugin`][crate::event::EventPlugin].
+pub struct InputMessagePlugin;
+
+impl Plugin for InputMessagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<KeyMessage>()
+            .add_message::<MouseMessage>()
+            .add_message::<FocusMessage>()
+            .add_message::<ResizeMessage>()
+            .add_message::<PasteMessage>()
+            .add_message::<CopyMessage>();
+
+        configure_input_sets(app);
+        app.add_systems(Startup, terminal_size_setup.after(context_setup))
+            .add_systems(
+                PreUpdate,
+                update_terminal_size.in_set(InputSet::EmitCrossterm),
+            );
+    }
+}
+
+/// Orders the public input extension points in the schedule that emits terminal messages.
+fn configure_input_sets(app: &mut App) {
+    app.configure_sets(
+        PreUpdate,
+        (
+            InputSet::Pre,
+            InputSet::EmitCrossterm,
+            InputSet::CheckEmulation,
+            InputSet::EmitBevy,
+            InputSet::Post,
+        )
+            .chain(),
+    );
+}
+
+/// Input-processing phases ordered within [`PreUpdate`].
+///
+/// Bevy configures system-set ordering separately for each schedule. Systems using these extension
+/// points must therefore be added to `PreUpdate` for the ordering below to apply:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::event::{InputSet, KeyMessage};
+/// # fn handle_keys(_: MessageReader<KeyMessage>) {}
+/// # let mut app = App::new();
+/// app.add_systems(PreUpdate, handle_keys.in_set(InputSet::Post));
+/// ```
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum InputSet {
+    /// Run before any input messages are emitted.
+    Pre,
+    /// Emit the crossterm messages, or their equivalents in windowed mode.
+    EmitCrossterm,
+    /// Check for emulation.
+    CheckEmulation,
+    /// Emit the bevy messages if [`crate::translation::TranslationPlugin`] has been added.
+    EmitBevy,
+    /// Run after all input messages are emitted.
+    Post,
+}
+
+/// A message that is sent whenever a key is pressed, repeated or released.
+#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyMessage(pub event::KeyEvent);
+
+/// A message that is sent whenever the mouse is used over the terminal.
+///
+/// The event's `column` and `row` are always in cells, even when the terminal was asked to report
+/// pixel coordinates.
+#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MouseMessage {
+    #[deref]
+    pub event: event::MouseEvent,
+    /// Position of the pointer in pixels, relative to the top-left of the terminal.
+    ///
+    /// Present in windowed mode, in logical window pixels (or in pixels of the terminal's image when
+    /// the pointer is given in UV coordinates through `TerminalPointer`), and in the crossterm
+    /// context when SGR-pixel tracking was enabled through `MousePlugin::pixel_coordinates`.
+    pub pixel_position: Option<UVec2>,
+}
+
+impl MouseMessage {
+    /// Creates a message for an event reported in cell coordinates.
+    pub fn new(event: event::MouseEvent) -> Self {
+        Self {
+            event,
+            pixel_position: None,
+        }
+    }
+}
+
+/// A message that is sent when the terminal gains or loses focus.
+#[derive(Message, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FocusMessage {
+    Gained,
+    Lost,
+}
+
+/// An event that is sent when the terminal is resized.
+#[derive(Message, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ResizeMessage(pub Size);
+
+/// An event that is sent when text is pasted into the terminal.
+///
+/// Sent by the crossterm context when the terminal pastes with bracketed paste, and in windowed
+/// mode when the clipboard is pasted with Ctrl+Shift+V, Shift+Insert or Cmd+V.
+#[derive(Message, Deref, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PasteMessage(pub String);
+
+/// A message that puts text on the system clipboard, e.g. to copy a selection.
+///
+/// The crossterm context asks the terminal to copy it with an OSC 52 escape sequence, which not
+/// every terminal supports. Windowed mode sets the clipboard directly, except on the web.
+#[derive(Message, Deref, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CopyMessage(pub String);
+
+/// The current size of the terminal in cells.
+///
+/// Updated in [`InputSet::EmitCrossterm`] whenever the terminal is resized, so systems that only
+/// redraw when something changed can use `resource_changed::<TerminalSize>` as a run condition
+/// instead of querying the terminal themselves.
+#[derive(Resource, Deref, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TerminalSize(pub Size);
+
+/// Startup system that records the initial [`TerminalSize`].
+fn terminal_size_setup(mut commands: Commands, context: Res<RatatuiContext>) -> Result {
+    commands.insert_resource(TerminalSize(context.size()?));
+    Ok(())
+}
+
+/// Keeps [`TerminalSize`] in sync with the latest [`ResizeMessage`].
+///
+/// Systems that send [`ResizeMessage`]s run before this one.
+pub(crate) fn update_terminal_size(
+    mut resize: MessageReader<ResizeMessage>,
+    mut size: ResMut<TerminalSize>,
+) {
+    if let Some(message) = resize.read().last() {
+        size.set_if_neq(TerminalSize(**message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Execution trace used to verify the schedule contract exposed by [`InputSet`].
+    #[derive(Default, Resource)]
+    struct InputOrder(Vec<InputSet>);
+
+    #[test]
+    fn input_sets_run_in_order_during_pre_update() {
+        let mut app = App::new();
+        configure_input_sets(&mut app);
+        app.init_resource::<InputOrder>()
+            .add_systems(PreUpdate, record_pre.in_set(InputSet::Pre))
+            .add_systems(PreUpdate, record_crossterm.in_set(InputSet::EmitCrossterm))
+            .add_systems(PreUpdate, record_emulation.in_set(InputSet::CheckEmulation))
+            .add_systems(PreUpdate, record_bevy.in_set(InputSet::EmitBevy))
+            .add_systems(PreUpdate, record_post.in_set(InputSet::Post));
+
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<InputOrder>().0,
+            [
+                InputSet::Pre,
+                InputSet::EmitCrossterm,
+                InputSet::CheckEmulation,
+                InputSet::EmitBevy,
+                InputSet::Post,
+            ]
+        );
+    }
+
+    #[test]
+    fn terminal_size_follows_the_last_resize() {
+        let mut app = App::new();
+        app.add_message::<ResizeMessage>()
+            .insert_resource(TerminalSize(Size::new(80, 24)))
+            .add_systems(Update, update_terminal_size);
+        app.update();
+
+        app.world_mut()
+            .write_message(ResizeMessage(Size::new(100, 30)));
+        app.world_mut()
+            .write_message(ResizeMessage(Size::new(120, 40)));
+        app.update();
+        assert_eq!(**app.world().resource::<TerminalSize>(), Size::new(120, 40));
+        let changed = app.world().resource_ref::<TerminalSize>().last_changed();
+
+        // Resizing back and forth within a frame does not count as a change.
+        app.world_mut()
+            .write_message(ResizeMessage(Size::new(100, 30)));
+        app.world_mut()
+            .write_message(ResizeMessage(Size::new(120, 40)));
+        app.update();
+        let size = app.world().resource_ref::<TerminalSize>();
+        assert_eq!(size.last_changed(), changed);
+    }
+
+    fn record_pre(mut order: ResMut<InputOrder>) {
+        order.0.push(InputSet::Pre);
+    }
+
+    fn record_crossterm(mut order: ResMut<InputOrder>) {
+        order.0.push(InputSet::EmitCrossterm);
+    }
+
+    fn record_emulation(mut order: ResMut<InputOrder>) {
+        order.0.push(InputSet::CheckEmulation);
+    }
+
+    fn record_bevy(mut order: ResMut<InputOrder>) {
+        order.0.push(InputSet::EmitBevy);
+    }
+
+    fn record_post(mut order: ResMut<InputOrder>) {
+        order.0.push(InputSet::Post);
+    }
+}
@@ -0,0 +1,263 @@
+//! Layers drawn over a frame.
+//!
+//! This module provides [`TerminalLayer`], for popups, tooltips and modal dialogs. A layer starts
+//! out transparent, and only the cells drawn to cover what is beneath it, so it can be drawn
+//! without knowing what the frame under it holds.
+use ratatui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::Color,
+    widgets::{StatefulWidget, Widget},
+};
+
+/// The symbol of the cells of a layer nothing was drawn to. Widgets draw text, so they never write
+/// it.
+const TRANSPARENT: &str = "\u{0}";
+
+/// A buffer drawn over a frame, where cells nothing was drawn to are transparent.
+///
+/// Compositing a layer onto a buffer with [`composite`][Self::composite]:
+/// - leaves the cells beneath transparent cells as they are,
+/// - only changes the style of the cells beneath cells that were styled without writing text to
+///   them, e.g. by a [`Block`][ratatui::widgets::Block] with a background, keeping their text,
+/// - replaces the cells beneath cells with text, including the blanks written by
+///   [`Clear`][ratatui::widgets::Clear].
+///
+/// The backgrounds of the layer are blended over those beneath with its
+/// [background alpha](Self::set_background_alpha), e.g. to shade the frame behind a modal dialog.
+/// Named and indexed colors are blended as xterm draws them, and the terminal's default colors
+/// can't be blended, so they are shown when their side has the most weight. The layers the crate
+/// composites in windowed mode are blended with the colors of the windowed terminal's
+/// `TerminalPalette` instead, default colors included.
+///
+/// Layers of the shared frame are drawn with
+/// [`TerminalFrame::layer`][crate::render::TerminalFrame::layer], and can also be composited onto
+/// the frame of [`RatatuiContext::draw`][crate::RatatuiContext] by hand:
+///
+/// ```
+/// # use ratatui::{Frame, widgets::Block};
+/// # use bevy_ratatui::render::TerminalLayer;
+/// fn draw_popup(frame: &mut Frame) {
+///     let mut popup = TerminalLayer::new(frame.area());
+///     popup.render_widget(Block::bordered().title("Saved"), frame.area());
+///     popup.composite(frame.buffer_mut());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalLayer {
+    buffer: Buffer,
+    background_alpha: f32,
+}
+
+impl TerminalLayer {
+    /// A transparent layer covering `area`, with opaque backgrounds.
+    pub fn new(area: Rect) -> Self {
+        Self {
+            buffer: Buffer::filled(area, Cell::new(TRANSPARENT)),
+            background_alpha: 1.0,
+        }
+    }
+
+    /// The area the layer covers.
+    pub fn area(&self) -> Rect {
+        self.buffer.area
+    }
+
+    /// How much the backgrounds of the layer cover those beneath, from `0.0` for not at all to
+    /// `1.0`, the default, for completely.
+    pub fn background_alpha(&self) -> f32 {
+        self.background_alpha
+    }
+
+    /// Sets how much the backgrounds of the layer cover those beneath. See
+    /// [`background_alpha`][Self::background_alpha].
+    pub fn set_background_alpha(&mut self, alpha: f32) {
+        self.background_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    /// Renders a widget in an area of the layer, like
+    /// [`Frame::render_widget`][ratatui::Frame::render_widget].
+    pub fn render_widget<W: Widget>(&mut self, widget: W, area: Rect) {
+        widget.render(area, &mut self.buffer);
+    }
+
+    /// Renders a widget with its state in an area of the layer, like
+    /// [`Frame::render_stateful_widget`][ratatui::Frame::render_stateful_widget].
+    pub fn render_stateful_widget<W: StatefulWidget>(
+        &mut self,
+        widget: W,
+        area: Rect,
+        state: &mut W::State,
+    ) {
+        widget.render(area, &mut self.buffer, state);
+    }
+
+    /// The buffer of the layer, for drawing to it directly.
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffer
+    }
+
+    /// Draws the layer over the cells of `target` it covers.
+    pub fn composite(&self, target: &mut Buffer) {
+        self.composite_with(target, |color| indexed_rgb(color, &XTERM_COLORS));
+    }
+
+    /// Draws the layer over the cells of `target` it covers, blending backgrounds as the RGB
+    /// values `rgb` gives them, and not at all those it gives none.
+    pub(crate) fn composite_with(
+        &self,
+        target: &mut Buffer,
+        rgb: impl Fn(Color) -> Option<[u8; 3]>,
+    ) {
+        for position in self.buffer.area.intersection(target.area).positions() {
+            let above = &self.buffer[position];
+            let below = &mut target[position];
+            if above.symbol() != TRANSPARENT {
+                let bg = blend(below.bg, above.bg, self.background_alpha, &rgb);
+                *below = above.clone();
+                below.bg = bg;
+                continue;
+            }
+            // Styled without text: the text beneath shows through.
+            if above.fg != Color::Reset {
+                below.fg = above.fg;
+            }
+            if above.bg != Color::Reset {
+                below.bg = blend(below.bg, above.bg, self.background_alpha, &rgb);
+            }
+            below.modifier |= above.modifier;
+        }
+    }
+}
+
+/// Blends the color `above` over `below` with `alpha`, as the RGB values `rgb` gives them.
+fn blend(below: Color, above: Color, alpha: f32, rgb: &impl Fn(Color) -> Option<[u8; 3]>) -> Color {
+    match (rgb(below), rgb(above)) {
+        _ if alpha >= 1.0 => above,
+        (Some(below), Some(above)) => {
+            let [r, g, b] = [0, 1, 2].map(|channel| {
+                let (below, above) = (f32::from(below[channel]), f32::from(above[channel]));
+                (below + (above - below) * alpha).round() as u8
+            });
+            Color::Rgb(r, g, b)
+        }
+        _ if alpha >= 0.5 => above,
+        _ => below,
+    }
+}
+
+/// The 256 indexed colors as xterm draws them by default: the 16 ANSI colors from
+/// [`Color::Black`] to [`Color::White`], then the 6×6×6 color cube and 24 shades of gray.
+pub(crate) const XTERM_COLORS: [[u8; 3]; 256] = {
+    const ANSI: [[u8; 3]; 16] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+        [127, 127, 127],
+        [255, 0, 0],
+        [0, 255, 0],
+        [255, 255, 0],
+        [92, 92, 255],
+        [255, 0, 255],
+        [0, 255, 255],
+        [255, 255, 255],
+    ];
+    const fn level(value: usize) -> u8 {
+        if value == 0 { 0 } else { 55 + value as u8 * 40 }
+    }
+    let mut colors = [[0; 3]; 256];
+    let mut index = 0;
+    while index < 256 {
+        colors[index] = match index {
+            0..16 => ANSI[index],
+            16..232 => {
+                let cube = index - 16;
+                [level(cube / 36), level(cube / 6 % 6), level(cube % 6)]
+            }
+            _ => [8 + (index - 232) as u8 * 10; 3],
+        };
+        index += 1;
+    }
+    colors
+};
+
+/// The RGB value of a color with these indexed `colors`, or `None` for [`Color::Reset`].
+pub(crate) fn indexed_rgb(color: Color, colors: &[[u8; 3]; 256]) -> Option<[u8; 3]> {
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some([r, g, b]),
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    Some(colors[index as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        style::{Style, Stylize},
+        widgets::{Block, Clear},
+    };
+
+    use super::*;
+
+    #[test]
+    fn only_what_was_drawn_covers_the_cells_beneath() {
+        let area = Rect::new(0, 0, 4, 1);
+        let mut frame = Buffer::with_lines(["abcd".red()]);
+        let mut layer = TerminalLayer::new(area);
+        layer.render_widget("X", Rect::new(0, 0, 1, 1));
+        layer.render_widget(Clear, Rect::new(1, 0, 1, 1));
+        layer.render_widget(Block::new().on_blue(), Rect::new(2, 0, 1, 1));
+        layer.composite(&mut frame);
+
+        let mut expected = Buffer::with_lines(["X cd"]);
+        expected.set_style(Rect::new(2, 0, 2, 1), Style::new().red());
+        expected.set_style(Rect::new(2, 0, 1, 1), Style::new().on_blue());
+        assert_eq!(frame, expected);
+    }
+
+    #[test]
+    fn backgrounds_are_blended_with_the_alpha() {
+        let area = Rect::new(0, 0, 2, 1);
+        let mut frame = Buffer::empty(area);
+        frame.set_style(area, Style::new().bg(Color::Rgb(0, 0, 200)));
+        let mut layer = TerminalLayer::new(area);
+        layer.set_background_alpha(0.25);
+        layer.render_widget(Block::new().bg(Color::Rgb(200, 100, 0)), area);
+        layer.render_widget("Y", Rect::new(1, 0, 1, 1));
+        layer.composite(&mut frame);
+
+        assert_eq!(frame[(0, 0)].bg, Color::Rgb(50, 25, 150));
+        assert_eq!(frame[(1, 0)].symbol(), "Y");
+        assert_eq!(frame[(1, 0)].bg, Color::Rgb(50, 25, 150));
+        // The terminal's default background can't be blended.
+        let xterm = |color| indexed_rgb(color, &XTERM_COLORS);
+        assert_eq!(blend(Color::Reset, Color::Red, 0.25, &xterm), Color::Reset);
+        assert_eq!(blend(Color::Reset, Color::Red, 0.75, &xterm), Color::Red);
+        assert_eq!(
+            blend(Color::Indexed(196), Color::Indexed(232), 0.5, &xterm),
+            Color::Rgb(132, 4, 4)
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Layouts of the terminal built from entities, resolved with Ratatui's [`Layout`] every frame.
+//!
+//! A [`TerminalNode`] splits its [`CellRect`] between its children, in the order of their
+//! [`Children`], so pane-based UIs can be declared as an entity hierarchy rather than computed in
+//! every draw.
+use bevy::prelude::*;
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+
+use crate::{
+    RatatuiContext,
+    render::RenderSet,
+    shared_frame::{SharedFrame, begin_shared_frame},
+    widget_components::{RenderedWidget, WidgetArea},
+};
+
+/// Plugin that lays out the [`TerminalNode`]s before anything is drawn in the
+/// [`RenderSet`]s. Added by the [`RatatuiPlugins`][crate::RatatuiPlugins].
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TerminalNode>()
+            .register_type::<CellRect>()
+            .add_systems(
+                PostUpdate,
+                frame_area
+                    .pipe(lay_out_terminal_nodes)
+                    .after(begin_shared_frame::<RatatuiContext>)
+                    .before(RenderSet::Draw),
+            );
+    }
+}
+
+/// Splits the [`CellRect`] of its entity between the entity's children, like a Ratatui
+/// [`Layout`].
+///
+/// A node that isn't the child of another fills the terminal, and follows it when it is resized.
+/// Its children, nodes or not, are given a [`CellRect`] each, from the area inside its margin: the
+/// first child the area of the first constraint, and so on. Children beyond the constraints are
+/// given an empty area, and without any constraints every child is given the whole area. Children
+/// that are [widget entities][crate::widget::TerminalWidget] are drawn in their [`CellRect`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::{layout::TerminalNode, widget::TerminalWidget};
+/// # use ratatui::{layout::{Constraint, Margin}, widgets::{Block, Paragraph}};
+/// fn spawn_panes(mut commands: Commands) {
+///     commands.spawn((
+///         TerminalNode::vertical([Constraint::Length(1), Constraint::Fill(1)]),
+///         children![
+///             TerminalWidget(Paragraph::new("Title")),
+///             (
+///                 TerminalNode {
+///                     margin: Margin::new(1, 1),
+///                     spacing: 1,
+///                     ..TerminalNode::horizontal([Constraint::Fill(1); 2])
+///                 },
+///                 TerminalWidget(Block::bordered()),
+///                 children![
+///                     TerminalWidget(Paragraph::new("Left")),
+///                     TerminalWidget(Paragraph::new("Right")),
+///                 ],
+///             ),
+///         ],
+///     ));
+/// }
+/// ```
+///
+/// Reflected as an opaque value, since Ratatui's layout types aren't [`Reflect`].
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(opaque)]
+#[reflect(Component, Default, Debug, PartialEq, Hash, Clone)]
+#[require(CellRect)]
+pub struct TerminalNode {
+    /// Whether the children are placed side by side or one above the other.
+    pub direction: Direction,
+    /// The sizes of the children, in order.
+    pub constraints: Vec<Constraint>,
+    /// The cells left around the children, e.g. for the borders of a [`Block`] drawn by the node.
+    ///
+    /// [`Block`]: ratatui::widgets::Block
+    pub margin: Margin,
+    /// The cells left between the children.
+    pub spacing: u16,
+}
+
+impl TerminalNode {
+    /// A node placing its children side by side.
+    pub fn horizontal<I>(constraints: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Constraint>,
+    {
+        Self::new(Direction::Horizontal, constraints)
+    }
+
+    /// A node placing its children one above the other.
+    pub fn vertical<I>(constraints: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Constraint>,
+    {
+        Self::new(Direction::Vertical, constraints)
+    }
+
+    /// A node placing its children in a direction, without margin or spacing.
+    pub fn new<I>(direction: Direction, constraints: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Constraint>,
+    {
+        Self {
+            direction,
+            constraints: constraints.into_iter().map(Into::into).collect(),
+            ..default()
+        }
+    }
+
+    /// The areas of the node's children within `area`.
+    fn split(&self, area: Rect, children: usize) -> Vec<Rect> {
+        let inner = area.inner(self.margin);
+        if self.constraints.is_empty() {
+            return vec![inner; children];
+        }
+        let areas = Layout::new(self.direction, self.constraints.iter().copied())
+            .spacing(self.spacing)
+            .split(inner);
+        (0..children)
+            .map(|index| areas.get(index).copied().unwrap_or_default())
+            .collect()
+    }
+}
+
+/// The cells an entity was laid out in by a [`TerminalNode`], in the terminal's coordinates.
+///
+/// Kept up to date every frame, before anything is drawn, and only changed when the area does.
+/// Mouse input can be matched to the entities under the pointer with it:
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::{event::MouseMessage, layout::CellRect};
+/// # use ratatui::layout::Position;
+/// #[derive(Component)]
+/// struct Hovered;
+///
+/// fn hover_panes(
+///     mut commands: Commands,
+///     mut mouse: MessageReader<MouseMessage>,
+///     panes: Query<(Entity, &CellRect)>,
+/// ) {
+///     let Some(message) = mouse.read().last() else {
+///         return;
+///     };
+///     let position = Position::new(message.column, message.row);
+///     for (pane, rect) in &panes {
+///         if rect.contains(position) {
+///             commands.entity(pane).insert(Hovered);
+///         } else {
+///             commands.entity(pane).remove::<Hovered>();
+///         }
+///     }
+/// }
+/// ```
+///
+/// Reflected as an opaque value, since Ratatui's [`Rect`] isn't [`Reflect`].
+#[derive(Component, Reflect, Deref, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(opaque)]
+#[reflect(Component, Default, Debug, PartialEq, Hash, Clone)]
+pub struct CellRect(pub Rect);
+
+/// The area of the terminal, as sized for this frame.
+fn frame_area(frame: Res<SharedFrame>) -> Rect {
+    frame.area()
+}
+
+/// System that gives the entities in the trees of [`TerminalNode`]s their [`CellRect`]s, the root
+/// nodes filling `area`.
+fn lay_out_terminal_nodes(
+    In(area): In<Rect>,
+    mut commands: Commands,
+    nodes: Query<(Entity, &TerminalNode, Option<&ChildOf>, Option<&Children>)>,
+    mut rects: Query<(
+        Option<&mut CellRect>,
+        Option<&mut WidgetArea>,
+        Has<RenderedWidget>,
+    )>,
+) {
+    let mut placed: Vec<(Entity, Rect)> = nodes
+        .iter()
+        .filter(|(.., parent, _)| parent.is_none_or(|parent| !nodes.contains(parent.parent())))
+        .map(|(entity, ..)| (entity, area))
+        .collect();
+    while let Some((entity, rect)) = placed.pop() {
+        if let Ok((cell_rect, widget_area, is_widget)) = rects.get_mut(entity) {
+            match cell_rect {
+                Some(mut cell_rect) => {
+                    cell_rect.set_if_neq(CellRect(rect));
+                }
+                None => {
+                    commands.entity(entity).insert(CellRect(rect));
+                }
+            }
+            match widget_area {
+                Some(mut widget_area) => {
+                    widget_area.set_if_neq(WidgetArea(rect));
+                }
+                None if is_widget => {
+                    commands.entity(entity).insert(WidgetArea(rect));
+                }
+                None => {}
+            }
+        }
+        let Ok((_, node, _, Some(children))) = nodes.get(entity) else {
+            continue;
+        };
+        let areas = node.split(rect, children.len());
+        placed.extend(children.iter().zip(areas));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use super::*;
+
+    fn rect(app: &App, entity: Entity) -> Rect {
+        app.world().get::<CellRect>(entity).unwrap().0
+    }
+
+    #[test]
+    fn children_are_laid_out_within_their_node() {
+        let mut app = App::new();
+        app.insert_resource(AreaForTest(Rect::new(0, 0, 21, 10)))
+            .add_systems(Update, area_for_test.pipe(lay_out_terminal_nodes));
+        let world = app.world_mut();
+        let title = world.spawn(RenderedWidget::default()).id();
+        let left = world.spawn_empty().id();
+        let right = world.spawn_empty().id();
+        let body = world
+            .spawn(TerminalNode {
+                margin: Margin::new(1, 1),
+                spacing: 1,
+                ..TerminalNode::horizontal([Constraint::Fill(1); 2])
+            })
+            .add_children(&[left, right])
+            .id();
+        let extra = world.spawn_empty().id();
+        let root = world
+            .spawn(TerminalNode::vertical([
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ]))
+            .add_children(&[title, body, extra])
+            .id();
+        app.update();
+
+        assert_eq!(rect(&app, root), Rect::new(0, 0, 21, 10));
+        assert_eq!(rect(&app, title), Rect::new(0, 0, 21, 1));
+        assert_eq!(
+            app.world().get::<WidgetArea>(title),
+            Some(&WidgetArea(Rect::new(0, 0, 21, 1)))
+        );
+        assert_eq!(rect(&app, body), Rect::new(0, 1, 21, 9));
+        assert_eq!(rect(&app, left), Rect::new(1, 2, 9, 7));
+        assert_eq!(rect(&app, right), Rect::new(11, 2, 9, 7));
+        assert_eq!(rect(&app, extra), Rect::ZERO);
+    }
+
+    #[test]
+    fn nodes_follow_the_terminal_size() {
+        let mut app = App::new();
+        app.insert_resource(AreaForTest(Rect::new(0, 0, 10, 4)))
+            .add_systems(Update, area_for_test.pipe(lay_out_terminal_nodes));
+        let world = app.world_mut();
+        let child = world.spawn_empty().id();
+        let root = world.spawn(TerminalNode::default()).add_child(child).id();
+        app.update();
+        let changed = app
+            .world()
+            .entity(root)
+            .get_ref::<CellRect>()
+            .unwrap()
+            .last_changed();
+
+        app.update();
+        let unchanged = app.world().entity(root).get_ref::<CellRect>().unwrap();
+        assert_eq!(unchanged.last_changed(), changed);
+
+        app.insert_resource(AreaForTest(Rect::new(0, 0, 30, 8)));
+        app.update();
+        assert_eq!(rect(&app, root), Rect::new(0, 0, 30, 8));
+        assert_eq!(rect(&app, child), Rect::new(0, 0, 30, 8));
+    }
+
+    #[test]
+    fn nodes_and_rects_are_reflected() {
+        let mut app = App::new();
+        app.add_plugins(LayoutPlugin);
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        assert!(
+            registry
+                .get_type_data::<ReflectComponent>(TypeId::of::<TerminalNode>())
+                .is_some()
+        );
+        assert!(
+            registry
+                .get_type_data::<ReflectComponent>(TypeId::of::<CellRect>())
+                .is_some()
+        );
+
+        let node = TerminalNode::vertical([Constraint::Min(1)]);
+        let reflected = TerminalNode::from_reflect(node.as_partial_reflect());
+        assert_eq!(reflected, Some(node));
+    }
+
+    #[derive(Resource)]
+    struct AreaForTest(Rect);
+
+    fn area_for_test(area: Res<AreaForTest>) -> Rect {
+        area.0
+    }
+}
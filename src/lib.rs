@@ -58,8 +58,13 @@
 mod context_trait;
 #[cfg(feature = "crossterm")]
 mod crossterm_context;
+mod input;
+mod layer;
+mod layout_tree;
 mod ratatui_context;
 mod ratatui_plugin;
+mod shared_frame;
+mod widget_components;
 #[cfg(feature = "windowed")]
 mod windowed_context;
 
@@ -84,16 +89,22 @@ pub mod cleanup {
     pub use super::crossterm_context::cleanup::CleanupPlugin;
 }
 
+#[cfg(feature = "crossterm")]
+pub mod clipboard {
+    pub use super::crossterm_context::clipboard::ClipboardPlugin;
+}
+
 #[cfg(feature = "crossterm")]
 pub mod error {
     pub use super::crossterm_context::error::ErrorPlugin;
 }
 
-#[cfg(feature = "crossterm")]
 pub mod event {
-    pub use super::crossterm_context::event::{
-        CrosstermMessage, EventPlugin, FocusMessage, InputSet, KeyMessage, MouseMessage,
-        PasteMessage, ResizeMessage,
+    #[cfg(feature = "crossterm")]
+    pub use super::crossterm_context::event::{CrosstermMessage, EventPlugin, ResizePolicy};
+    pub use super::input::{
+        CopyMessage, FocusMessage, InputMessagePlugin, InputSet, KeyMessage, MouseMessage,
+        PasteMessage, ResizeMessage, TerminalSize,
     };
 }
 
@@ -104,16 +115,67 @@ pub mod kitty {
 
 #[cfg(all(feature = "crossterm", feature = "mouse"))]
 pub mod mouse {
-    pub use super::crossterm_context::mouse::{MouseEnabled, MousePlugin};
+    pub use super::crossterm_context::mouse::{
+        MouseEnabled, MousePlugin, MouseReporting, MouseTracking,
+    };
+}
+
+#[cfg(all(feature = "crossterm", feature = "bevy_window"))]
+pub mod terminal_window {
+    pub use super::crossterm_context::window::{
+        CursorCoordinates, TerminalWindow, TerminalWindowPlugin,
+    };
+}
+
+pub mod layout {
+    pub use super::layout_tree::{CellRect, LayoutPlugin, TerminalNode};
+}
+
+pub mod render {
+    pub use super::{
+        layer::TerminalLayer,
+        shared_frame::{FramePlugin, RenderSet, TerminalFrame},
+    };
 }
 
 #[cfg(feature = "crossterm")]
 pub mod translation {
-    #[cfg(feature = "keyboard")]
+    #[cfg(any(feature = "keyboard", feature = "mouse"))]
     pub use super::crossterm_context::translation::*;
 }
 
+pub mod widget {
+    pub use super::widget_components::{
+        StatefulTerminalWidget, TerminalWidget, TerminalWidgetAppExt, WidgetArea, WidgetPlugin,
+        WidgetZIndex,
+    };
+}
+
 #[cfg(feature = "windowed")]
 pub mod windowed {
-    pub use super::windowed_context::plugin::WindowedPlugin;
+    #[cfg(feature = "recording")]
+    pub use super::windowed_context::recording::{
+        RecordingError, RecordingFormat, RecordingPlugin, StartRecordingMessage,
+        StopRecordingMessage,
+    };
+    pub use super::windowed_context::{
+        accessibility::{
+            AccessibleRegion, RegionRole, TerminalAccessibilityNode, WindowedAccessibilityPlugin,
+        },
+        clipboard::WindowedClipboardPlugin,
+        cursor::{CursorShape, WindowedCursor},
+        font::{FontError, FontSource, TerminalFont, WindowedFont, WindowedFontPlugin},
+        input::WindowedInputPlugin,
+        overlay::{CellOverlay, OverlayLayer},
+        palette::{PaletteError, TerminalPalette},
+        plugin::{TerminalImage, WindowedPlugin},
+        scaling::{ScalingMode, WindowedScaling},
+        screenshot::{
+            FrameDumpMessage, ScreenshotError, ScreenshotMessage, WindowedScreenshotPlugin,
+            encode_png, save_png,
+        },
+        texture::TerminalTexture,
+        viewport::{TerminalImageNode, TerminalPointer, TerminalViewport},
+        window_terminal::{WindowTerminal, WindowTerminalMessage},
+    };
 }
@@ -3,7 +3,10 @@ use bevy::{
     prelude::{Commands, Result},
 };
 
-use crate::{RatatuiContext, context::DefaultContext};
+use crate::{
+    RatatuiContext, context::DefaultContext, layout::LayoutPlugin, render::FramePlugin,
+    widget::WidgetPlugin,
+};
 
 use crate::context::TerminalContext;
 
@@ -40,7 +43,11 @@ impl PluginGroup for RatatuiPlugins {
     fn build(self) -> PluginGroupBuilder {
         let mut builder = PluginGroupBuilder::start::<Self>();
 
-        builder = builder.add(ContextPlugin);
+        builder = builder
+            .add(ContextPlugin)
+            .add(FramePlugin)
+            .add(WidgetPlugin)
+            .add(LayoutPlugin);
 
         builder = DefaultContext::configure_plugin_group(&self, builder);
 
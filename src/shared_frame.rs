@@ -0,0 +1,414 @@
+//! Drawing a frame from many systems.
+//!
+//! This module provides the [`FramePlugin`] and the [`TerminalFrame`] system parameter. Systems
+//! draw through [`TerminalFrame`] into a buffer kept for the whole of [`PostUpdate`], which is
+//! drawn to the terminal with [`RatatuiContext`] once, in [`RenderSet::Flush`], with its
+//! [`TerminalLayer`]s composited over it.
+use std::{collections::BTreeMap, mem};
+
+use bevy::{
+    ecs::{component::Mutable, system::SystemParam},
+    prelude::*,
+};
+use ratatui::{
+    Frame,
+    buffer::Buffer,
+    layout::{Position, Rect, Size},
+    widgets::{StatefulWidget, Widget},
+};
+
+use crate::{
+    RatatuiContext,
+    layer::{TerminalLayer, XTERM_COLORS, indexed_rgb},
+};
+
+/// Plugin that lets many systems draw into one frame through [`TerminalFrame`], flushing it to the
+/// terminal once at the end of the [`RenderSet`]s. Added by the
+/// [`RatatuiPlugins`][crate::RatatuiPlugins].
+pub struct FramePlugin;
+
+impl Plugin for FramePlugin {
+    fn build(&self, app: &mut App) {
+        add_shared_frame::<RatatuiContext>(app);
+    }
+}
+
+/// Adds the shared frame, drawn to the terminal `T`.
+fn add_shared_frame<T: FrameTerminal>(app: &mut App) {
+    configure_render_sets(app);
+    app.init_resource::<SharedFrame>().add_systems(
+        PostUpdate,
+        (
+            begin_shared_frame::<T>.before(RenderSet::Draw),
+            flush_shared_frame::<T>
+                .in_set(RenderSet::Flush)
+                .run_if(|frame: Res<SharedFrame>| frame.drawn),
+        ),
+    );
+}
+
+/// A terminal the shared frame is drawn to: the [`RatatuiContext`], or a test terminal.
+pub(crate) trait FrameTerminal: Resource + Component<Mutability = Mutable> {
+    /// The size of the terminal, in cells.
+    fn size(&self) -> Result<Size>;
+
+    /// Draws a complete frame, like [`Terminal::draw`][ratatui::Terminal::draw].
+    fn draw_frame(&mut self, render: impl FnOnce(&mut Frame)) -> Result;
+}
+
+impl FrameTerminal for RatatuiContext {
+    fn size(&self) -> Result<Size> {
+        Ok(self.0.size()?)
+    }
+
+    fn draw_frame(&mut self, render: impl FnOnce(&mut Frame)) -> Result {
+        self.0.draw(render)?;
+        Ok(())
+    }
+}
+
+/// Orders the drawing phases of the shared frame within [`PostUpdate`].
+fn configure_render_sets(app: &mut App) {
+    app.configure_sets(
+        PostUpdate,
+        (RenderSet::Draw, RenderSet::Overlay, RenderSet::Flush).chain(),
+    );
+}
+
+/// Drawing phases of the shared frame, ordered within [`PostUpdate`].
+///
+/// Systems drawing with a [`TerminalFrame`] go in [`Draw`][Self::Draw] or
+/// [`Overlay`][Self::Overlay], and the frame is flushed to the terminal in
+/// [`Flush`][Self::Flush]. Systems within a phase draw in no particular order, so widgets that
+/// must cover others go in a later phase, are ordered explicitly, or are drawn to a
+/// [layer](TerminalFrame::layer):
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::render::{RenderSet, TerminalFrame};
+/// # fn draw_map(_: TerminalFrame) {}
+/// # fn draw_hud(_: TerminalFrame) {}
+/// # fn draw_popup(_: TerminalFrame) {}
+/// # let mut app = App::new();
+/// app.add_systems(
+///     PostUpdate,
+///     (
+///         (draw_map, draw_hud.after(draw_map)).in_set(RenderSet::Draw),
+///         draw_popup.in_set(RenderSet::Overlay),
+///     ),
+/// );
+/// ```
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum RenderSet {
+    /// Draw the frame, e.g. the main view, panels and HUDs.
+    Draw,
+    /// Draw over what was drawn in [`Draw`][Self::Draw], e.g. popups.
+    Overlay,
+    /// The frame is drawn to the terminal. Systems that need the terminal's finished frame run
+    /// after this set.
+    Flush,
+}
+
+/// A frame shared by all the systems drawing with [`TerminalFrame`] during [`PostUpdate`].
+#[derive(Resource, Debug, Default)]
+pub(crate) struct SharedFrame {
+    buffer: Buffer,
+    cursor: Option<Position>,
+    /// Composited over the buffer from the lowest z-index up.
+    layers: BTreeMap<i32, TerminalLayer>,
+    /// Whether anything was drawn this frame, so frames drawn with [`RatatuiContext`] are left
+    /// alone otherwise.
+    drawn: bool,
+}
+
+impl SharedFrame {
+    /// The area of the whole terminal, as sized for this frame.
+    pub(crate) fn area(&self) -> Rect {
+        self.buffer.area
+    }
+}
+
+/// A system parameter for drawing part of the terminal's frame, alongside the other systems
+/// drawing in the [`RenderSet`]s.
+///
+/// Unlike drawing with [`RatatuiContext`], where each call to `draw` is a complete frame,
+/// everything drawn through a `TerminalFrame` during [`PostUpdate`] makes up a single frame drawn
+/// to the terminal at the end of the [`RenderSet`]s, so independent plugins can each draw their
+/// own widgets. The frame starts out empty, and replaces one drawn with [`RatatuiContext`]
+/// earlier in the same update; on updates where nothing is drawn through it, the terminal is left
+/// alone.
+///
+/// Widgets that must cover whatever else is drawn, such as modal dialogs, can be drawn to a
+/// [`layer`][Self::layer] instead, without knowing what is beneath them.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::render::{RenderSet, TerminalFrame};
+/// # use ratatui::{layout::Rect, widgets::Block};
+/// fn draw_log_panel(mut frame: TerminalFrame) {
+///     let area = frame.area();
+///     let height = area.height.min(5);
+///     let panel = Rect { y: area.bottom() - height, height, ..area };
+///     frame.render_widget(Block::bordered().title("Log"), panel);
+/// }
+/// # let mut app = App::new();
+/// app.add_systems(PostUpdate, draw_log_panel.in_set(RenderSet::Draw));
+/// ```
+#[derive(SystemParam)]
+pub struct TerminalFrame<'w> {
+    frame: ResMut<'w, SharedFrame>,
+}
+
+impl TerminalFrame<'_> {
+    /// The area of the whole terminal.
+    pub fn area(&self) -> Rect {
+        self.frame.area()
+    }
+
+    /// Renders a widget in an area of the frame, like
+    /// [`Frame::render_widget`][ratatui::Frame::render_widget].
+    pub fn render_widget<W: Widget>(&mut self, widget: W, area: Rect) {
+        widget.render(area, self.buffer_mut());
+    }
+
+    /// Renders a widget with its state in an area of the frame, like
+    /// [`Frame::render_stateful_widget`][ratatui::Frame::render_stateful_widget].
+    pub fn render_stateful_widget<W: StatefulWidget>(
+        &mut self,
+        widget: W,
+        area: Rect,
+        state: &mut W::State,
+    ) {
+        widget.render(area, self.buffer_mut(), state);
+    }
+
+    /// Shows the cursor at a position once the frame is drawn. The last position set wins.
+    pub fn set_cursor_position<P: Into<Position>>(&mut self, position: P) {
+        self.frame.drawn = true;
+        self.frame.cursor = Some(position.into());
+    }
+
+    /// The buffer of the frame, for drawing to it directly.
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        self.frame.drawn = true;
+        &mut self.frame.buffer
+    }
+
+    /// The layer of the frame at a z-index, covering the whole terminal, created transparent the
+    /// first time it is drawn to in a frame.
+    ///
+    /// Layers are composited over what is drawn to the frame itself once everything is drawn,
+    /// those with a higher z-index over those with a lower one, whichever order the systems
+    /// drawing them ran in.
+    ///
+    /// ```
+    /// # use bevy_ratatui::render::TerminalFrame;
+    /// # use ratatui::{layout::Constraint, style::Stylize, widgets::{Block, Clear}};
+    /// fn draw_quit_dialog(mut frame: TerminalFrame) {
+    ///     let area = frame.area();
+    ///     let shade = frame.layer(10);
+    ///     shade.set_background_alpha(0.5);
+    ///     shade.render_widget(Block::new().on_black(), area);
+    ///
+    ///     let dialog = area.centered(Constraint::Length(20), Constraint::Length(3));
+    ///     let layer = frame.layer(11);
+    ///     layer.render_widget(Clear, dialog);
+    ///     layer.render_widget(Block::bordered().title("Quit?"), dialog);
+    /// }
+    /// ```
+    pub fn layer(&mut self, z_index: i32) -> &mut TerminalLayer {
+        let area = self.area();
+        self.frame.drawn = true;
+        self.frame
+            .layers
+            .entry(z_index)
+            .or_insert_with(|| TerminalLayer::new(area))
+    }
+}
+
+/// Clears the shared frame, sized to the terminal, before anything is drawn to it.
+pub(crate) fn begin_shared_frame<T: FrameTerminal>(
+    context: Res<T>,
+    mut frame: ResMut<SharedFrame>,
+) -> Result {
+    let area = Rect::from((Position::ORIGIN, context.size()?));
+    let frame = frame.bypass_change_detection();
+    frame.buffer.resize(area);
+    frame.buffer.reset();
+    frame.cursor = None;
+    frame.layers.clear();
+    frame.drawn = false;
+    Ok(())
+}
+
+/// The colors the backgrounds of layers are blended as: those of the
+/// [`TerminalPalette`](crate::windowed::TerminalPalette) when there is one, xterm's otherwise.
+#[derive(SystemParam)]
+pub(crate) struct LayerColors<'w> {
+    #[cfg(feature = "windowed")]
+    palette: Option<Res<'w, crate::windowed::TerminalPalette>>,
+    #[cfg(not(feature = "windowed"))]
+    _world: std::marker::PhantomData<&'w ()>,
+}
+
+impl LayerColors<'_> {
+    /// Draws `layer` over the cells of `target` it covers, blending its backgrounds with these
+    /// colors.
+    pub(crate) fn composite(&self, layer: &TerminalLayer, target: &mut Buffer) {
+        #[cfg(feature = "windowed")]
+        if let Some(palette) = &self.palette {
+            layer.composite_with(target, |color| Some(palette.background(color)));
+            return;
+        }
+        layer.composite_with(target, |color| indexed_rgb(color, &XTERM_COLORS));
+    }
+}
+
+/// Draws the shared frame to the terminal.
+pub(crate) fn flush_shared_frame<T: FrameTerminal>(
+    mut context: ResMut<T>,
+    mut frame: ResMut<SharedFrame>,
+    colors: LayerColors,
+) -> Result {
+    let frame = &mut *frame;
+    for layer in frame.layers.values() {
+        colors.composite(layer, &mut frame.buffer);
+    }
+    context.draw_frame(|terminal_frame| {
+        let buffer = terminal_frame.buffer_mut();
+        if buffer.area == frame.buffer.area {
+            mem::swap(buffer, &mut frame.buffer);
+        } else {
+            // The terminal was resized since the frame began.
+            copy_cells(&frame.buffer, buffer);
+        }
+        if let Some(cursor) = frame.cursor {
+            terminal_frame.set_cursor_position(cursor);
+        }
+    })
+}
+
+/// Copies the cells two buffers have in common.
+fn copy_cells(from: &Buffer, to: &mut Buffer) {
+    for position in from.area.intersection(to.area).positions() {
+        to[position] = from[position].clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend, style::Style};
+
+    use super::*;
+
+    /// A terminal counting the frames drawn to it.
+    #[derive(Resource)]
+    struct TestTerminal {
+        terminal: Terminal<TestBackend>,
+        frames: usize,
+    }
+
+    impl FrameTerminal for TestTerminal {
+        fn size(&self) -> Result<Size> {
+            Ok(self.terminal.size()?)
+        }
+
+        fn draw_frame(&mut self, render: impl FnOnce(&mut Frame)) -> Result {
+            self.frames += 1;
+            self.terminal.draw(render)?;
+            Ok(())
+        }
+    }
+
+    fn app(width: u16, height: u16) -> App {
+        let mut app = App::new();
+        app.insert_resource(TestTerminal {
+            terminal: Terminal::new(TestBackend::new(width, height)).unwrap(),
+            frames: 0,
+        });
+        add_shared_frame::<TestTerminal>(&mut app);
+        app
+    }
+
+    fn drawn(app: &App) -> (&Buffer, usize) {
+        let terminal = app.world().resource::<TestTerminal>();
+        (terminal.terminal.backend().buffer(), terminal.frames)
+    }
+
+    #[test]
+    fn systems_in_the_render_sets_draw_one_frame() {
+        let mut app = app(4, 2);
+        app.add_systems(
+            PostUpdate,
+            (
+                (
+                    |mut frame: TerminalFrame| {
+                        frame.buffer_mut().set_string(0, 0, "aaaa", Style::new())
+                    },
+                    |mut frame: TerminalFrame| {
+                        frame.buffer_mut().set_string(0, 1, "bb", Style::new())
+                    },
+                )
+                    .in_set(RenderSet::Draw),
+                (|mut frame: TerminalFrame| frame.buffer_mut().set_string(1, 0, "c", Style::new()))
+                    .in_set(RenderSet::Overlay),
+            ),
+        );
+        app.update();
+
+        assert_eq!(drawn(&app), (&Buffer::with_lines(["acaa", "bb  "]), 1));
+    }
+
+    #[test]
+    fn layers_are_composited_in_z_order() {
+        let mut app = app(2, 1);
+        app.add_systems(
+            PostUpdate,
+            (
+                |mut frame: TerminalFrame| {
+                    frame
+                        .layer(2)
+                        .buffer_mut()
+                        .set_string(0, 0, "x", Style::new())
+                },
+                |mut frame: TerminalFrame| {
+                    frame
+                        .layer(1)
+                        .buffer_mut()
+                        .set_string(0, 0, "yy", Style::new())
+                },
+            )
+                .chain()
+                .in_set(RenderSet::Draw),
+        );
+        app.update();
+
+        assert_eq!(drawn(&app), (&Buffer::with_lines(["xy"]), 1));
+    }
+
+    #[test]
+    fn frames_drawn_directly_are_left_alone() {
+        let mut app = app(3, 1);
+        app.add_systems(Update, |mut terminal: ResMut<TestTerminal>| {
+            terminal
+                .draw_frame(|frame| frame.buffer_mut().set_string(0, 0, "abc", Style::new()))
+                .unwrap();
+        });
+        app.update();
+        app.update();
+
+        assert_eq!(drawn(&app), (&Buffer::with_lines(["abc"]), 2));
+    }
+
+    #[test]
+    fn common_cells_are_copied() {
+        let mut from = Buffer::with_lines(["abc", "def"]);
+        let mut to = Buffer::empty(Rect::new(0, 0, 2, 3));
+        copy_cells(&from, &mut to);
+        assert_eq!(to, Buffer::with_lines(["ab", "de", "  "]));
+
+        from.resize(Rect::new(0, 0, 1, 1));
+        copy_cells(&to, &mut from);
+        assert_eq!(from, Buffer::with_lines(["a"]));
+    }
+}
@@ -0,0 +1,369 @@
+//! Widgets kept in the ECS as components, drawn to the shared frame by the plugin.
+//!
+//! Each widget entity keeps what its widget rendered, and is only rendered again when its widget
+//! or its area changes; the frame is put together from what was rendered every frame.
+use bevy::prelude::*;
+use ratatui::{
+    layout::Rect,
+    widgets::{
+        BarChart, Block, Chart, Gauge, LineGauge, List, Paragraph, Sparkline, StatefulWidget,
+        Table, Tabs, Widget,
+    },
+};
+
+use crate::{
+    layer::TerminalLayer,
+    render::{RenderSet, TerminalFrame},
+    shared_frame::LayerColors,
+};
+
+/// Plugin that renders the [`TerminalWidget`]s and [`StatefulTerminalWidget`]s of Ratatui's
+/// widgets to the shared frame in [`RenderSet::Draw`]. Added by the
+/// [`RatatuiPlugins`][crate::RatatuiPlugins].
+///
+/// Other widgets are registered with [`TerminalWidgetAppExt`].
+pub struct WidgetPlugin;
+
+impl Plugin for WidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<WidgetArea>()
+            .register_type::<WidgetZIndex>()
+            .add_observer(clear_rendered_widget::<WidgetArea>)
+            .add_systems(
+                PostUpdate,
+                draw_widgets
+                    .in_set(RenderSet::Draw)
+                    .run_if(any_with_component::<RenderedWidget>),
+            )
+            .register_terminal_widget::<Block<'static>>()
+            .register_terminal_widget::<Paragraph<'static>>()
+            .register_terminal_widget::<List<'static>>()
+            .register_terminal_widget::<Table<'static>>()
+            .register_terminal_widget::<Gauge<'static>>()
+            .register_terminal_widget::<LineGauge<'static>>()
+            .register_terminal_widget::<Tabs<'static>>()
+            .register_terminal_widget::<Sparkline<'static>>()
+            .register_terminal_widget::<BarChart<'static>>()
+            .register_terminal_widget::<Chart<'static>>()
+            .register_stateful_terminal_widget::<List<'static>>()
+            .register_stateful_terminal_widget::<Table<'static>>();
+    }
+}
+
+/// Registers the systems rendering widget components of other widget types, such as the app's
+/// own.
+pub trait TerminalWidgetAppExt {
+    /// Renders the [`TerminalWidget`]s of a widget type.
+    fn register_terminal_widget<W>(&mut self) -> &mut Self
+    where
+        W: Send + Sync + 'static,
+        for<'a> &'a W: Widget;
+
+    /// Renders the [`StatefulTerminalWidget`]s of a widget type.
+    fn register_stateful_terminal_widget<W>(&mut self) -> &mut Self
+    where
+        W: StatefulWidget + Send + Sync + 'static,
+        W::State: Sized + Send + Sync,
+        for<'a> &'a W: StatefulWidget<State = W::State>;
+}
+
+impl TerminalWidgetAppExt for App {
+    fn register_terminal_widget<W>(&mut self) -> &mut Self
+    where
+        W: Send + Sync + 'static,
+        for<'a> &'a W: Widget,
+    {
+        self.add_observer(clear_rendered_widget::<TerminalWidget<W>>)
+            .add_systems(
+                PostUpdate,
+                render_widgets::<W>
+                    .in_set(RenderSet::Draw)
+                    .before(draw_widgets),
+            )
+    }
+
+    fn register_stateful_terminal_widget<W>(&mut self) -> &mut Self
+    where
+        W: StatefulWidget + Send + Sync + 'static,
+        W::State: Sized + Send + Sync,
+        for<'a> &'a W: StatefulWidget<State = W::State>,
+    {
+        self.add_observer(clear_rendered_widget::<StatefulTerminalWidget<W>>)
+            .add_systems(
+                PostUpdate,
+                render_stateful_widgets::<W>
+                    .in_set(RenderSet::Draw)
+                    .before(draw_widgets),
+            )
+    }
+}
+
+/// A widget drawn by the plugin in the [`WidgetArea`] of its entity, every frame until it is
+/// removed.
+///
+/// The widget is only rendered again when it or its area changes, so it can be kept in the ECS
+/// and changed like any other component, e.g. by the systems handling input. An entity has one
+/// widget; entities drawn in the same place are ordered by their [`WidgetZIndex`]. Widgets of
+/// types other than Ratatui's are registered with [`TerminalWidgetAppExt`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::widget::{TerminalWidget, WidgetArea};
+/// # use ratatui::{layout::Rect, widgets::{Gauge, Paragraph}};
+/// #[derive(Component)]
+/// struct Health;
+///
+/// fn spawn_hud(mut commands: Commands) {
+///     commands.spawn((
+///         TerminalWidget(Paragraph::new("Player 1")),
+///         WidgetArea(Rect::new(0, 0, 20, 1)),
+///     ));
+///     commands.spawn((
+///         Health,
+///         TerminalWidget(Gauge::default().ratio(1.0)),
+///         WidgetArea(Rect::new(0, 1, 20, 1)),
+///     ));
+/// }
+///
+/// fn hurt(mut health: Single<&mut TerminalWidget<Gauge<'static>>, With<Health>>) {
+///     health.0 = Gauge::default().ratio(0.5);
+/// }
+/// ```
+///
+/// Unlike [`WidgetArea`] and [`WidgetZIndex`], widget components aren't [`Reflect`]: Ratatui's
+/// widgets aren't, and a type would have to be registered for every widget type anyway.
+#[derive(Component, Deref, DerefMut, Debug, Clone, Default)]
+#[require(RenderedWidget)]
+pub struct TerminalWidget<W: Send + Sync + 'static>(pub W);
+
+/// A widget drawn with its state by the plugin in the [`WidgetArea`] of its entity, like a
+/// [`TerminalWidget`].
+///
+/// Changing the state, e.g. selecting an item of a list, renders the widget again. The state is
+/// also updated by rendering, e.g. with the scroll offset of a list, without counting as a change.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::widget::StatefulTerminalWidget;
+/// # use ratatui::widgets::List;
+/// fn select_next(mut menus: Query<&mut StatefulTerminalWidget<List<'static>>>) {
+///     for mut menu in &mut menus {
+///         menu.state.select_next();
+///     }
+/// }
+/// ```
+///
+/// Like [`TerminalWidget`], it isn't [`Reflect`], since neither Ratatui's widgets nor their
+/// states are.
+#[derive(Component, Debug, Clone, Default)]
+#[require(RenderedWidget)]
+pub struct StatefulTerminalWidget<W>
+where
+    W: StatefulWidget + Send + Sync + 'static,
+    W::State: Sized + Send + Sync,
+{
+    /// The widget.
+    pub widget: W,
+    /// The state it is rendered with.
+    pub state: W::State,
+}
+
+/// The cells a widget entity is drawn in, in the terminal's coordinates. Widgets without one
+/// aren't drawn.
+///
+/// Widgets laid out by a [`TerminalNode`][crate::layout::TerminalNode] are given the
+/// [`CellRect`][crate::layout::CellRect] they were laid out in.
+///
+/// Reflected as an opaque value, since Ratatui's [`Rect`] isn't [`Reflect`].
+#[derive(Component, Reflect, Deref, DerefMut, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(opaque)]
+#[reflect(Component, Default, Debug, PartialEq, Hash, Clone)]
+pub struct WidgetArea(pub Rect);
+
+/// The order of widget entities drawn in the same place, those with a higher index being drawn
+/// over those with a lower one. Without one, an entity's index is `0`.
+///
+/// Among entities with the same index, children are drawn over their parents, e.g. the contents
+/// of a panel over its [`Block`], and others in no particular order.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[reflect(Component, Default, Debug, PartialEq, Hash, Clone)]
+pub struct WidgetZIndex(pub i32);
+
+/// What the widget of an entity rendered, composited onto the frame every frame.
+#[derive(Component, Debug)]
+pub(crate) struct RenderedWidget(TerminalLayer);
+
+impl Default for RenderedWidget {
+    fn default() -> Self {
+        Self(TerminalLayer::new(Rect::ZERO))
+    }
+}
+
+/// System that renders the [`TerminalWidget`]s of a widget type that changed or moved.
+#[allow(clippy::type_complexity)]
+fn render_widgets<W>(
+    mut widgets: Query<
+        (&TerminalWidget<W>, &WidgetArea, &mut RenderedWidget),
+        Or<(Changed<TerminalWidget<W>>, Changed<WidgetArea>)>,
+    >,
+) where
+    W: Send + Sync + 'static,
+    for<'a> &'a W: Widget,
+{
+    for (widget, area, mut rendered) in &mut widgets {
+        let mut layer = TerminalLayer::new(area.0);
+        layer.render_widget(&widget.0, area.0);
+        rendered.0 = layer;
+    }
+}
+
+/// System that renders the [`StatefulTerminalWidget`]s of a widget type that changed or moved.
+#[allow(clippy::type_complexity)]
+fn render_stateful_widgets<W>(
+    mut widgets: Query<
+        (
+            &mut StatefulTerminalWidget<W>,
+            &WidgetArea,
+            &mut RenderedWidget,
+        ),
+        Or<(Changed<StatefulTerminalWidget<W>>, Changed<WidgetArea>)>,
+    >,
+) where
+    W: StatefulWidget + Send + Sync + 'static,
+    W::State: Sized + Send + Sync,
+    for<'a> &'a W: StatefulWidget<State = W::State>,
+{
+    for (mut widget, area, mut rendered) in &mut widgets {
+        // What rendering stores in the state isn't a change to render again for.
+        let widget = widget.bypass_change_detection();
+        let mut layer = TerminalLayer::new(area.0);
+        layer.render_stateful_widget(&widget.widget, area.0, &mut widget.state);
+        rendered.0 = layer;
+    }
+}
+
+/// Observer that stops drawing an entity whose widget or area was removed.
+fn clear_rendered_widget<C: Component>(
+    remove: On<Remove, C>,
+    mut rendered: Query<&mut RenderedWidget>,
+) {
+    if let Ok(mut rendered) = rendered.get_mut(remove.entity) {
+        *rendered = RenderedWidget::default();
+    }
+}
+
+/// System that draws what the widget entities rendered to the shared frame.
+fn draw_widgets(
+    mut frame: TerminalFrame,
+    colors: LayerColors,
+    widgets: Query<(Entity, &RenderedWidget, Option<&WidgetZIndex>)>,
+    parents: Query<&ChildOf>,
+) {
+    let mut widgets: Vec<_> = widgets
+        .iter()
+        .map(|(entity, rendered, z_index)| {
+            let depth = parents.iter_ancestors(entity).count();
+            ((z_index.copied().unwrap_or_default(), depth), &rendered.0)
+        })
+        .collect();
+    widgets.sort_by_key(|(order, _)| *order);
+    let buffer = frame.buffer_mut();
+    for (_, rendered) in widgets {
+        colors.composite(rendered, buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        buffer::Buffer,
+        widgets::{ListState, Widget},
+    };
+
+    use std::any::TypeId;
+
+    use super::*;
+
+    fn rendered(app: &App, entity: Entity) -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 2));
+        let rendered = app.world().get::<RenderedWidget>(entity).unwrap();
+        rendered.0.composite(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn widgets_are_rendered_again_when_changed() {
+        let mut app = App::new();
+        app.add_systems(Update, render_widgets::<Paragraph<'static>>);
+        let entity = app
+            .world_mut()
+            .spawn((
+                TerminalWidget(Paragraph::new("ab")),
+                WidgetArea(Rect::new(0, 0, 3, 1)),
+            ))
+            .id();
+        app.update();
+        assert_eq!(rendered(&app, entity), Buffer::with_lines(["ab ", "   "]));
+
+        // Left alone while neither the widget nor its area changes.
+        let mut layer = TerminalLayer::new(Rect::new(0, 0, 3, 1));
+        "x".render(Rect::new(0, 0, 1, 1), layer.buffer_mut());
+        app.world_mut().get_mut::<RenderedWidget>(entity).unwrap().0 = layer;
+        app.update();
+        assert_eq!(rendered(&app, entity), Buffer::with_lines(["x  ", "   "]));
+
+        app.world_mut().get_mut::<WidgetArea>(entity).unwrap().0 = Rect::new(1, 1, 2, 1);
+        app.update();
+        assert_eq!(rendered(&app, entity), Buffer::with_lines(["   ", " ab"]));
+    }
+
+    #[test]
+    fn rendering_the_state_is_not_a_change() {
+        let mut app = App::new();
+        app.add_systems(Update, render_stateful_widgets::<List<'static>>);
+        let entity = app
+            .world_mut()
+            .spawn((
+                StatefulTerminalWidget {
+                    widget: List::new(["a", "b", "c"]),
+                    state: ListState::default().with_selected(Some(2)),
+                },
+                WidgetArea(Rect::new(0, 0, 3, 2)),
+            ))
+            .id();
+        app.update();
+        assert_eq!(rendered(&app, entity), Buffer::with_lines(["b  ", "c  "]));
+
+        let world = app.world();
+        let widget = world.get::<StatefulTerminalWidget<List<'static>>>(entity);
+        assert_eq!(widget.unwrap().state.offset(), 1);
+        let ticks = world
+            .entity(entity)
+            .get_change_ticks::<StatefulTerminalWidget<List<'static>>>()
+            .unwrap();
+        assert_eq!(ticks.changed, ticks.added);
+    }
+
+    #[test]
+    fn areas_and_z_indices_are_reflected() {
+        let mut app = App::new();
+        app.add_plugins(WidgetPlugin);
+        let registry = app.world().resource::<AppTypeRegistry>().read();
+        assert!(
+            registry
+                .get_type_data::<ReflectComponent>(TypeId::of::<WidgetArea>())
+                .is_some()
+        );
+        assert!(
+            registry
+                .get_type_data::<ReflectComponent>(TypeId::of::<WidgetZIndex>())
+                .is_some()
+        );
+
+        let area = WidgetArea(Rect::new(1, 2, 3, 4));
+        assert_eq!(
+            WidgetArea::from_reflect(area.as_partial_reflect()),
+            Some(area)
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! Screen reader access to the terminal in windowed mode.
+//!
+//! The text of the terminal is exposed through Bevy's AccessKit integration: the whole buffer as
+//! a terminal node, and the [`AccessibleRegion`]s apps mark in it as nodes of their own, placed
+//! over their cells. Like any accessibility node, they are only read by assistive technology when
+//! one is running.
+use accesskit::{Live, Node, Role};
+use bevy::{
+    a11y::{AccessibilityNode, AccessibilitySystems},
+    input_focus::InputFocus,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use ratatui::{buffer::Buffer, layout};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{RatatuiContext, render::RenderSet};
+
+use super::viewport::TerminalViewport;
+
+/// Plugin that keeps the accessibility nodes of the terminal and of its [`AccessibleRegion`]s up
+/// to date with what was drawn. Added by the [`RatatuiPlugins`][crate::RatatuiPlugins] when using
+/// the windowed context.
+///
+/// The terminal's node is spawned at startup, marked with [`TerminalAccessibilityNode`].
+pub struct WindowedAccessibilityPlugin;
+
+impl Plugin for WindowedAccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputFocus>()
+            .add_observer(remove_region_node)
+            .add_systems(Startup, spawn_terminal_accessibility_node)
+            .add_systems(
+                PostUpdate,
+                update_terminal_accessibility
+                    .after(RenderSet::Flush)
+                    .before(AccessibilitySystems::Update),
+            );
+    }
+}
+
+/// Marks the accessibility node of the whole terminal, whose value is the text of the cells
+/// outside of any [`AccessibleRegion`].
+///
+/// Giving it the [`InputFocus`] has changes to that text announced.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct TerminalAccessibilityNode;
+
+/// Exposes an area of cells of the terminal to screen readers as a node of its own, e.g. the
+/// message list or the status line of a layout, rather than as part of the whole terminal's text.
+///
+/// The region's text is what is drawn in its cells, a line per row. Changes to it are announced
+/// while the entity has the [`InputFocus`].
+///
+/// ```no_run
+/// # use bevy::{input_focus::InputFocus, prelude::*};
+/// # use bevy_ratatui::windowed::{AccessibleRegion, RegionRole};
+/// # use ratatui::layout::Rect;
+/// fn setup(mut commands: Commands) {
+///     let log = commands
+///         .spawn(AccessibleRegion {
+///             area: Rect::new(0, 0, 80, 20),
+///             role: RegionRole::Log,
+///             label: Some("Messages".into()),
+///         })
+///         .id();
+///     commands.insert_resource(InputFocus::from_entity(log));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessibleRegion {
+    /// The cells of the region, in the terminal's coordinates.
+    pub area: layout::Rect,
+    /// What the region is to screen readers.
+    pub role: RegionRole,
+    /// The name screen readers give the region. Without one, its text is its name.
+    pub label: Option<String>,
+}
+
+/// See [`AccessibleRegion`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum RegionRole {
+    /// A named part of the screen.
+    #[default]
+    Region,
+    /// A heading, such as a title bar.
+    Heading,
+    /// A list of items.
+    List,
+    /// A table of rows.
+    Table,
+    /// A button.
+    Button,
+    /// A text field being edited.
+    TextInput,
+    /// A status line.
+    Status,
+    /// A log of messages, new ones being added at its end.
+    Log,
+}
+
+impl RegionRole {
+    fn role(self) -> Role {
+        match self {
+            RegionRole::Region => Role::Region,
+            RegionRole::Heading => Role::Heading,
+            RegionRole::List => Role::List,
+            RegionRole::Table => Role::Table,
+            RegionRole::Button => Role::Button,
+            RegionRole::TextInput => Role::TextInput,
+            RegionRole::Status => Role::Status,
+            RegionRole::Log => Role::Log,
+        }
+    }
+}
+
+fn spawn_terminal_accessibility_node(mut commands: Commands) {
+    commands.spawn((
+        TerminalAccessibilityNode,
+        AccessibilityNode::from(Node::new(Role::Terminal)),
+    ));
+}
+
+/// Observer that removes the accessibility node of an entity that is no longer a region.
+fn remove_region_node(remove: On<Remove, AccessibleRegion>, mut commands: Commands) {
+    commands
+        .entity(remove.entity)
+        .try_remove::<AccessibilityNode>();
+}
+
+/// System that updates the text, bounds and liveness of the terminal's accessibility nodes when
+/// something was drawn, moved or focused, leaving the nodes that stay the same untouched.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_terminal_accessibility(
+    mut commands: Commands,
+    context: Res<RatatuiContext>,
+    viewport: Res<TerminalViewport>,
+    focus: Res<InputFocus>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    terminal: Option<
+        Single<
+            (Entity, &mut AccessibilityNode),
+            (With<TerminalAccessibilityNode>, Without<AccessibleRegion>),
+        >,
+    >,
+    mut regions: Query<(
+        Entity,
+        Ref<AccessibleRegion>,
+        Option<&mut AccessibilityNode>,
+    )>,
+    mut removed: RemovedComponents<AccessibleRegion>,
+) {
+    let regions_changed = removed.read().count() > 0
+        || regions
+            .iter()
+            .any(|(_, region, node)| region.is_changed() || node.is_none());
+    if !context.is_changed() && !viewport.is_changed() && !focus.is_changed() && !regions_changed {
+        return;
+    }
+    let buffer = &context.backend().buffer;
+    // Without a window, e.g. when running headless, logical and physical pixels are the same.
+    let scale_factor = window.map_or(1.0, |window| window.scale_factor());
+    let live = |entity: Entity, node: &mut Node| {
+        if focus.get() == Some(entity) {
+            node.set_live(Live::Polite);
+        } else {
+            node.clear_live();
+        }
+    };
+
+    let mut areas = Vec::new();
+    for (entity, region, node) in &mut regions {
+        areas.push(region.area);
+        let text = area_text(buffer, region.area, &[]);
+        let mut accessible = Node::new(region.role.role());
+        match &region.label {
+            Some(label) => {
+                accessible.set_label(label.as_str());
+                accessible.set_value(text);
+            }
+            None => {
+                accessible.set_label(text);
+                accessible.clear_value();
+            }
+        }
+        accessible.set_bounds(bounds(viewport.area_rect(region.area), scale_factor));
+        live(entity, &mut accessible);
+        match node {
+            Some(node) => update_node(node, accessible),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(AccessibilityNode::from(accessible));
+            }
+        }
+    }
+
+    let Some(terminal) = terminal else {
+        return;
+    };
+    let (entity, node) = terminal.into_inner();
+    let mut accessible = node.0.clone();
+    accessible.set_value(area_text(buffer, buffer.area, &areas));
+    accessible.set_bounds(bounds(viewport.rect, scale_factor));
+    live(entity, &mut accessible);
+    update_node(node, accessible);
+}
+
+/// Replaces an accessibility node only if it differs, so that redrawing the terminal without
+/// changing its text doesn't update the accessibility tree.
+fn update_node(mut node: Mut<AccessibilityNode>, accessible: Node) {
+    if node.0 != accessible {
+        node.0 = accessible;
+    }
+}
+
+/// The text of the cells of `area` outside of the `excluded` areas, a line per row, without
+/// trailing blanks.
+fn area_text(buffer: &Buffer, area: layout::Rect, excluded: &[layout::Rect]) -> String {
+    let area = area.intersection(buffer.area);
+    let mut lines: Vec<String> = area
+        .rows()
+        .map(|row| {
+            let mut line = String::new();
+            let mut x = row.left();
+            while x < row.right() {
+                let position = layout::Position::new(x, row.y);
+                let symbol = buffer[position].symbol();
+                if excluded.iter().any(|area| area.contains(position)) {
+                    line.push(' ');
+                } else {
+                    line.push_str(symbol);
+                }
+                // The cells covered by a wide character hold blanks.
+                x += symbol.width().max(1) as u16;
+            }
+            line.trim_end().to_owned()
+        })
+        .collect();
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// The bounds of an area of the window in logical pixels, in the physical pixels AccessKit uses.
+fn bounds(rect: Rect, scale_factor: f32) -> accesskit::Rect {
+    let (min, max) = (rect.min * scale_factor, rect.max * scale_factor);
+    accesskit::Rect::new(min.x as f64, min.y as f64, max.x as f64, max.y as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, widgets::Widget};
+
+    use super::*;
+
+    #[test]
+    fn text_is_read_a_line_per_row() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 7, 4));
+        "ab 你好".render(Rect::new(0, 0, 7, 1), &mut buffer);
+        "status".render(Rect::new(0, 2, 7, 1), &mut buffer);
+
+        assert_eq!(area_text(&buffer, buffer.area, &[]), "ab 你好\n\nstatus");
+        assert_eq!(
+            area_text(&buffer, Rect::new(1, 0, 3, 3), &[]),
+            "b 你\n\ntat"
+        );
+    }
+
+    #[test]
+    fn regions_are_left_out_of_the_terminal_text() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        "title".render(Rect::new(0, 0, 6, 1), &mut buffer);
+        "status".render(Rect::new(0, 1, 6, 1), &mut buffer);
+
+        let status = Rect::new(0, 1, 6, 1);
+        assert_eq!(area_text(&buffer, buffer.area, &[status]), "title");
+    }
+}
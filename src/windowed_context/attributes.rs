@@ -0,0 +1,339 @@
+//! Text attributes in windowed mode.
+//!
+//! Attributes that only change colors, such as reversed or dim text, are resolved in the buffer
+//! before the soft backend rasterizes it. Lines and blinking are drawn over the terminal's image
+//! once the frame has been copied to it, with underlines in their own color. Blinking is taken out
+//! of the buffer too, so the backend doesn't blink text on a clock of its own.
+use std::{ops::Range, time::Duration};
+
+use bevy::prelude::*;
+use embedded_graphics::mono_font::{DecorationDimensions, MonoFont};
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::palette::TerminalPalette;
+
+/// How long blinking text stays shown, then hidden.
+const SLOW_BLINK: Duration = Duration::from_millis(800);
+const RAPID_BLINK: Duration = Duration::from_millis(250);
+
+/// Where underlines and strikethroughs are drawn in a cell, taken from the regular font.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct TextDecorations {
+    underline: DecorationDimensions,
+    strikethrough: DecorationDimensions,
+}
+
+impl TextDecorations {
+    /// The decorations of `font`.
+    pub(super) fn of(font: &MonoFont) -> Self {
+        Self {
+            underline: font.underline,
+            strikethrough: font.strikethrough,
+        }
+    }
+
+    /// Removes the decorations of `font`, so the backend draws none and they can be drawn in
+    /// their own colors.
+    pub(super) fn strip(font: MonoFont<'static>) -> MonoFont<'static> {
+        MonoFont {
+            underline: DecorationDimensions::new(0, 0),
+            strikethrough: DecorationDimensions::new(0, 0),
+            ..font
+        }
+    }
+}
+
+/// Replaces the colors of every cell of `buffer` by the RGB values they are drawn with, swapping
+/// them for reversed text, dimming the foreground of dim text and hiding hidden text. Blinking is
+/// taken out of the cells and returned, to be drawn by [`draw_attributes`].
+pub(super) fn resolve_colors(buffer: &mut Buffer, palette: &TerminalPalette) -> BlinkingText {
+    let rgb = |[r, g, b]: [u8; 3]| Color::Rgb(r, g, b);
+    let mut blinking = BlinkingText {
+        width: usize::from(buffer.area.width),
+        modifiers: Vec::new(),
+    };
+    let cells = buffer.content.len();
+    for (index, cell) in buffer.content.iter_mut().enumerate() {
+        let (mut fg, mut bg) = (palette.foreground(cell.fg), palette.background(cell.bg));
+        if cell.underline_color != Color::Reset {
+            cell.underline_color = rgb(palette.foreground(cell.underline_color));
+        }
+        if cell.modifier.contains(Modifier::REVERSED) {
+            (fg, bg) = (bg, fg);
+        }
+        if cell.modifier.contains(Modifier::DIM) {
+            fg = std::array::from_fn(|channel| {
+                ((u16::from(fg[channel]) + u16::from(bg[channel])) / 2) as u8
+            });
+        }
+        if cell.modifier.contains(Modifier::HIDDEN) {
+            fg = bg;
+        }
+        let blink = cell.modifier & (Modifier::SLOW_BLINK | Modifier::RAPID_BLINK);
+        if !blink.is_empty() {
+            blinking.modifiers.resize(cells, Modifier::empty());
+            blinking.modifiers[index] = blink;
+        }
+        cell.modifier -= Modifier::REVERSED | Modifier::DIM | blink;
+        cell.fg = rgb(fg);
+        cell.bg = rgb(bg);
+    }
+    blinking
+}
+
+/// The blinking modifiers of the cells of a buffer, taken out of it by [`resolve_colors`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct BlinkingText {
+    width: usize,
+    /// One per cell, or none when nothing blinks.
+    modifiers: Vec<Modifier>,
+}
+
+impl BlinkingText {
+    /// The rows with blinking text.
+    pub(super) fn rows(&self) -> impl Iterator<Item = u16> + '_ {
+        self.modifiers
+            .chunks(self.width.max(1))
+            .enumerate()
+            .filter(|(_, cells)| cells.iter().any(|modifier| !modifier.is_empty()))
+            .map(|(row, _)| row as u16)
+    }
+
+    /// The blinking modifiers of the cell at `column` and `row`.
+    fn at(&self, column: usize, row: usize) -> Modifier {
+        let index = row * self.width + column;
+        self.modifiers.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// Whether blinking text is shown at some point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct TextBlink {
+    slow: bool,
+    rapid: bool,
+}
+
+impl TextBlink {
+    /// The blinking text shown `elapsed` after the app started.
+    pub(super) fn at(elapsed: Duration) -> Self {
+        let shown =
+            |interval: Duration| (elapsed.as_millis() / interval.as_millis()).is_multiple_of(2);
+        Self {
+            slow: shown(SLOW_BLINK),
+            rapid: shown(RAPID_BLINK),
+        }
+    }
+
+    /// Whether a cell with these modifiers is hidden.
+    fn hides(self, modifier: Modifier) -> bool {
+        (modifier.contains(Modifier::SLOW_BLINK) && !self.slow)
+            || (modifier.contains(Modifier::RAPID_BLINK) && !self.rapid)
+    }
+}
+
+/// Draws the underlines, strikethroughs and blinking of the given `rows` of `buffer` over the RGBA
+/// `image` it was rendered to with cells of `cell_size` pixels, hiding the `blinking` text that
+/// `blink` doesn't show.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn draw_attributes(
+    buffer: &Buffer,
+    blinking: &BlinkingText,
+    rows: &[u16],
+    image: &mut [u8],
+    image_width: usize,
+    cell_size: UVec2,
+    decorations: TextDecorations,
+    blink: TextBlink,
+) {
+    let (cell_width, cell_height) = (cell_size.x as usize, cell_size.y as usize);
+    let columns = buffer.area.width as usize;
+    // Double-width characters are decorated across both of their cells.
+    let mut fill = |cell: (usize, usize, &Cell), lines: Range<usize>, color: Color| {
+        let (column, row, cell) = cell;
+        let Color::Rgb(r, g, b) = color else {
+            return;
+        };
+        let x0 = column * cell_width;
+        let cells = cell.symbol().width().clamp(1, 2);
+        let width = (cell_width * cells).min(image_width.saturating_sub(x0));
+        for y in lines.start.min(cell_height)..lines.end.min(cell_height) {
+            let start = ((row * cell_height + y) * image_width + x0) * 4;
+            let Some(pixels) = image.get_mut(start..start + width * 4) else {
+                return;
+            };
+            for rgba in pixels.chunks_exact_mut(4) {
+                rgba[..3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    };
+    let lines = |decoration: DecorationDimensions| {
+        let offset = decoration.offset as usize;
+        offset..offset + decoration.height as usize
+    };
+
+    for &row in rows {
+        let start = row as usize * columns;
+        let Some(cells) = buffer.content.get(start..start + columns) else {
+            continue;
+        };
+        for (column, cell) in cells.iter().enumerate() {
+            let at = (column, row as usize, cell);
+            if cell.modifier.contains(Modifier::HIDDEN) {
+                continue;
+            }
+            if blink.hides(blinking.at(column, row as usize)) {
+                fill(at, 0..cell_height, cell.bg);
+                continue;
+            }
+            if cell.modifier.contains(Modifier::UNDERLINED) {
+                let color = match cell.underline_color {
+                    Color::Reset => cell.fg,
+                    color => color,
+                };
+                fill(at, lines(decorations.underline), color);
+            }
+            if cell.modifier.contains(Modifier::CROSSED_OUT) {
+                fill(at, lines(decorations.strikethrough), cell.fg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, style::Style};
+
+    use super::{super::context::WindowedContext, *};
+    use crate::context::TerminalContext;
+
+    const CELL: UVec2 = UVec2::new(2, 4);
+    const DECORATIONS: TextDecorations = TextDecorations {
+        underline: DecorationDimensions::new(3, 1),
+        strikethrough: DecorationDimensions::new(1, 1),
+    };
+
+    /// The pixels of an image with one color, as (x, y) positions.
+    type Pixels = ([u8; 3], Vec<(usize, usize)>);
+
+    /// The pixels of a one-cell image with each color.
+    fn draw(style: Style, blink: TextBlink) -> Vec<Pixels> {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 1, 1));
+        buffer.set_string(0, 0, "x", style);
+        let blinking = resolve_colors(&mut buffer, &TerminalPalette::default());
+        let mut image = vec![0; 2 * 4 * 4];
+        draw_attributes(
+            &buffer,
+            &blinking,
+            &[0],
+            &mut image,
+            2,
+            CELL,
+            DECORATIONS,
+            blink,
+        );
+        let mut colors: Vec<Pixels> = Vec::new();
+        for (i, rgba) in image.chunks(4).enumerate() {
+            let color = [rgba[0], rgba[1], rgba[2]];
+            match colors.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, pixels)) => pixels.push((i % 2, i / 2)),
+                None => colors.push((color, vec![(i % 2, i / 2)])),
+            }
+        }
+        colors
+    }
+
+    #[test]
+    fn colors_are_resolved_before_rasterizing() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buffer[(0, 0)].set_style(Style::new().red().reversed());
+        buffer[(1, 0)].set_style(Style::new().fg(Color::Rgb(200, 100, 0)).dim());
+        buffer[(2, 0)].set_style(Style::new().on_blue().hidden());
+        resolve_colors(&mut buffer, &TerminalPalette::default());
+
+        assert_eq!(buffer[(0, 0)].fg, Color::Rgb(0, 0, 0));
+        assert_eq!(buffer[(0, 0)].bg, Color::Rgb(205, 0, 0));
+        assert_eq!(buffer[(1, 0)].fg, Color::Rgb(100, 50, 0));
+        assert_eq!(buffer[(2, 0)].fg, Color::Rgb(0, 0, 238));
+        assert!(!buffer[(0, 0)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn underlines_use_their_own_color() {
+        let style = Style::new()
+            .red()
+            .underlined()
+            .underline_color(Color::Green);
+        let colors = draw(style, TextBlink::default());
+        assert_eq!(colors[1], ([0, 205, 0], vec![(0, 3), (1, 3)]));
+
+        let colors = draw(Style::new().red().crossed_out(), TextBlink::default());
+        assert_eq!(colors[1], ([205, 0, 0], vec![(0, 1), (1, 1)]));
+    }
+
+    #[test]
+    fn blinking_text_is_hidden_half_of_the_time() {
+        let at = |millis| TextBlink::at(Duration::from_millis(millis));
+        assert_eq!(
+            at(0),
+            TextBlink {
+                slow: true,
+                rapid: true
+            }
+        );
+        assert_eq!(
+            at(300),
+            TextBlink {
+                slow: true,
+                rapid: false
+            }
+        );
+        assert_eq!(
+            at(1000),
+            TextBlink {
+                slow: false,
+                rapid: true
+            }
+        );
+
+        let style = Style::new().on_blue().underlined().slow_blink();
+        assert_eq!(draw(style, at(0)).len(), 2);
+        let hidden = draw(style, at(1000));
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0].0, [0, 0, 238]);
+    }
+
+    #[test]
+    fn blinking_text_is_lit_while_shown() {
+        let mut context = WindowedContext::init().unwrap();
+        // The backend would hide slowly blinking text on its next draw.
+        context.backend_mut().blink_counter = 19;
+        context
+            .draw(|frame| {
+                let style = Style::new().red().slow_blink();
+                frame.buffer_mut().set_string(0, 0, "\u{2588}", style);
+            })
+            .unwrap();
+        let backend = context.backend();
+        let mut image = backend.get_pixmap_data_as_rgba();
+        let width = backend.get_pixmap_width();
+        let cell_size = UVec2::new(backend.char_width as u32, backend.char_height as u32);
+        let center = (cell_size.y as usize / 2 * width + cell_size.x as usize / 2) * 4;
+        let shown = TextBlink::at(Duration::ZERO);
+        draw_attributes(
+            &backend.buffer,
+            context.blinking(),
+            &[0],
+            &mut image,
+            width,
+            cell_size,
+            context.decorations(),
+            shown,
+        );
+        assert_eq!(image[center..center + 3], [205, 0, 0]);
+        assert_eq!(context.blinking().rows().collect::<Vec<_>>(), [0]);
+    }
+}
@@ -0,0 +1,141 @@
+//! The system clipboard in windowed mode.
+//!
+//! A window gets no paste events from the OS, so the clipboard is read when the keys terminals
+//! paste with are pressed, and sent as a [`PasteMessage`][crate::event::PasteMessage].
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+use ratatui::crossterm::event::KeyModifiers;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::input::CopyMessage;
+use crate::input::InputMessagePlugin;
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+use crate::input::{InputSet, PasteMessage};
+
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+use super::keyboard_translation::held_modifiers;
+
+/// Plugin that pastes the system clipboard as a [`PasteMessage`][crate::event::PasteMessage] on
+/// Ctrl+Shift+V, Shift+Insert or Cmd+V, and puts the text of
+/// [`CopyMessage`][crate::event::CopyMessage]s on the clipboard.
+///
+/// The key presses are still sent as [`KeyMessage`][crate::event::KeyMessage]s. The clipboard
+/// can't be used on the web, where nothing is pasted or copied.
+pub struct WindowedClipboardPlugin;
+
+impl Plugin for WindowedClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<InputMessagePlugin>() {
+            app.add_plugins(InputMessagePlugin);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.init_non_send::<SystemClipboard>()
+                .add_systems(PostUpdate, copy_to_clipboard);
+            #[cfg(feature = "keyboard")]
+            app.add_systems(
+                PreUpdate,
+                paste_from_clipboard
+                    .after(InputSystems)
+                    .in_set(InputSet::EmitCrossterm),
+            );
+        }
+    }
+}
+
+/// The OS clipboard, opened on first use. Kept open, as some platforms only serve copied text
+/// while the clipboard that copied it exists.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct SystemClipboard(Option<arboard::Clipboard>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SystemClipboard {
+    fn get(&mut self) -> Option<&mut arboard::Clipboard> {
+        if self.0.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.0 = Some(clipboard),
+                Err(error) => tracing::warn!("Failed to open the clipboard: {error}"),
+            }
+        }
+        self.0.as_mut()
+    }
+}
+
+/// Whether the keys terminals paste with were just pressed.
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+fn paste_pressed(keys: &ButtonInput<KeyCode>) -> bool {
+    let modifiers = held_modifiers(keys);
+    let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+    (keys.just_pressed(KeyCode::KeyV) && [ctrl_shift, KeyModifiers::SUPER].contains(&modifiers))
+        || (keys.just_pressed(KeyCode::Insert) && modifiers == KeyModifiers::SHIFT)
+}
+
+#[cfg(all(feature = "keyboard", not(target_arch = "wasm32")))]
+fn paste_from_clipboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    mut paste: MessageWriter<PasteMessage>,
+) {
+    if !paste_pressed(&keys) {
+        return;
+    }
+    let Some(clipboard) = clipboard.get() else {
+        return;
+    };
+    match clipboard.get_text() {
+        Ok(text) if !text.is_empty() => {
+            paste.write(PasteMessage(text));
+        }
+        Ok(_) | Err(arboard::Error::ContentNotAvailable) => {}
+        Err(error) => tracing::warn!("Failed to paste from the clipboard: {error}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_to_clipboard(
+    mut copies: MessageReader<CopyMessage>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+) {
+    // Only the last copy would end up on the clipboard.
+    let Some(CopyMessage(text)) = copies.read().last() else {
+        return;
+    };
+    let Some(clipboard) = clipboard.get() else {
+        return;
+    };
+    if let Err(error) = clipboard.set_text(text.as_str()) {
+        tracing::warn!("Failed to copy to the clipboard: {error}");
+    }
+}
+
+#[cfg(all(test, feature = "keyboard", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn pressed(held: &[KeyCode], key: KeyCode) -> bool {
+        let mut keys = ButtonInput::default();
+        for &code in held {
+            keys.press(code);
+        }
+        keys.clear();
+        keys.press(key);
+        paste_pressed(&keys)
+    }
+
+    #[test]
+    fn terminal_paste_shortcuts_paste() {
+        use KeyCode as k;
+        assert!(pressed(&[k::ControlLeft, k::ShiftRight], k::KeyV));
+        assert!(pressed(&[k::SuperLeft], k::KeyV));
+        assert!(pressed(&[k::ShiftLeft], k::Insert));
+        assert!(!pressed(&[k::ControlLeft], k::KeyV));
+        assert!(!pressed(
+            &[k::ControlLeft, k::AltLeft, k::ShiftLeft],
+            k::KeyV
+        ));
+        assert!(!pressed(&[k::ControlLeft, k::ShiftLeft], k::KeyC));
+    }
+}
@@ -2,7 +2,8 @@ use std::fmt::Debug;
 
 use bevy::prelude::*;
 
-use ratatui::Terminal;
+use embedded_graphics::mono_font::MonoFont;
+use ratatui::{CompletedFrame, Frame, Terminal, backend::Backend, layout::Size};
 
 use crate::context::TerminalContext;
 use soft_ratatui::embedded_graphics_unicodefonts::{
@@ -10,12 +11,93 @@ use soft_ratatui::embedded_graphics_unicodefonts::{
 };
 use soft_ratatui::{EmbeddedGraphics, SoftBackend};
 
-use super::plugin::WindowedPlugin;
+use super::{
+    accessibility::WindowedAccessibilityPlugin,
+    attributes::{BlinkingText, TextDecorations, resolve_colors},
+    clipboard::WindowedClipboardPlugin,
+    font::WindowedFontPlugin,
+    input::WindowedInputPlugin,
+    palette::TerminalPalette,
+    plugin::WindowedPlugin,
+    screenshot::WindowedScreenshotPlugin,
+};
 
 /// Ratatui context that will set up a window and render the ratatui buffer using a 2D texture,
 /// instead of drawing to a terminal buffer.
 #[derive(Deref, DerefMut)]
-pub struct WindowedContext(Terminal<SoftBackend<EmbeddedGraphics>>);
+pub struct WindowedContext {
+    #[deref]
+    terminal: Terminal<SoftBackend<EmbeddedGraphics>>,
+    palette: TerminalPalette,
+    decorations: TextDecorations,
+    blinking: BlinkingText,
+}
+
+impl WindowedContext {
+    /// Draws a single frame, like [`Terminal::draw`], with its colors mapped through the
+    /// [`TerminalPalette`] and its text attributes drawn like a terminal would.
+    pub fn draw<F>(
+        &mut self,
+        render_callback: F,
+    ) -> Result<CompletedFrame<'_>, <SoftBackend<EmbeddedGraphics> as Backend>::Error>
+    where
+        F: FnOnce(&mut Frame),
+    {
+        let (palette, blinking) = (&self.palette, &mut self.blinking);
+        self.terminal.draw(|frame| {
+            render_callback(frame);
+            *blinking = resolve_colors(frame.buffer_mut(), palette);
+        })
+    }
+
+    /// Tries to draw a single frame, like [`Terminal::try_draw`], with its colors mapped through
+    /// the [`TerminalPalette`] and its text attributes drawn like a terminal would.
+    pub fn try_draw<F, E>(
+        &mut self,
+        render_callback: F,
+    ) -> Result<CompletedFrame<'_>, <SoftBackend<EmbeddedGraphics> as Backend>::Error>
+    where
+        F: FnOnce(&mut Frame) -> Result<(), E>,
+        E: Into<<SoftBackend<EmbeddedGraphics> as Backend>::Error>,
+    {
+        let (palette, blinking) = (&self.palette, &mut self.blinking);
+        self.terminal.try_draw(|frame| {
+            render_callback(frame)?;
+            *blinking = resolve_colors(frame.buffer_mut(), palette);
+            Ok::<(), E>(())
+        })
+    }
+
+    /// Replaces the backend with one drawing `cells` with these fonts, e.g. to draw with different
+    /// fonts.
+    pub(crate) fn set_fonts(
+        &mut self,
+        cells: Size,
+        regular: MonoFont<'static>,
+        bold: Option<MonoFont<'static>>,
+        italic: Option<MonoFont<'static>>,
+    ) -> Result {
+        let (backend, decorations) = soft_backend(cells, regular, bold, italic);
+        self.terminal = Terminal::new(backend)?;
+        self.decorations = decorations;
+        Ok(())
+    }
+
+    /// Where underlines and strikethroughs are drawn in the cells of the current fonts.
+    pub(super) fn decorations(&self) -> TextDecorations {
+        self.decorations
+    }
+
+    /// The blinking text of the last frame drawn.
+    pub(super) fn blinking(&self) -> &BlinkingText {
+        &self.blinking
+    }
+
+    /// Replaces the palette used by the next frames drawn.
+    pub(crate) fn set_palette(&mut self, palette: TerminalPalette) {
+        self.palette = palette;
+    }
+}
 
 impl Debug for WindowedContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -25,18 +107,19 @@ impl Debug for WindowedContext {
 
 impl TerminalContext<SoftBackend<EmbeddedGraphics>> for WindowedContext {
     fn init() -> Result<Self> {
-        let font_regular = mono_8x13_atlas();
-        let font_italic = mono_8x13_italic_atlas();
-        let font_bold = mono_8x13_bold_atlas();
-        let backend = SoftBackend::<EmbeddedGraphics>::new(
-            100,
-            50,
-            font_regular,
-            Some(font_bold),
-            Some(font_italic),
+        let (backend, decorations) = soft_backend(
+            Size::new(100, 50),
+            mono_8x13_atlas(),
+            Some(mono_8x13_bold_atlas()),
+            Some(mono_8x13_italic_atlas()),
         );
         let terminal = Terminal::new(backend)?;
-        Ok(Self(terminal))
+        Ok(Self {
+            terminal,
+            palette: TerminalPalette::default(),
+            decorations,
+            blinking: BlinkingText::default(),
+        })
     }
 
     fn restore() -> Result<()> {
@@ -47,8 +130,33 @@ impl TerminalContext<SoftBackend<EmbeddedGraphics>> for WindowedContext {
         _group: &crate::RatatuiPlugins,
         mut builder: bevy::app::PluginGroupBuilder,
     ) -> bevy::app::PluginGroupBuilder {
-        builder = builder.add(WindowedPlugin);
+        builder = builder
+            .add(WindowedPlugin::default())
+            .add(WindowedInputPlugin)
+            .add(WindowedClipboardPlugin)
+            .add(WindowedFontPlugin::default())
+            .add(WindowedScreenshotPlugin::default())
+            .add(WindowedAccessibilityPlugin);
 
         builder
     }
 }
+
+/// Creates a soft backend drawing `cells` with these fonts. Text decorations are left out of the
+/// fonts, and returned to be drawn separately.
+fn soft_backend(
+    cells: Size,
+    regular: MonoFont<'static>,
+    bold: Option<MonoFont<'static>>,
+    italic: Option<MonoFont<'static>>,
+) -> (SoftBackend<EmbeddedGraphics>, TextDecorations) {
+    let decorations = TextDecorations::of(&regular);
+    let backend = SoftBackend::<EmbeddedGraphics>::new(
+        cells.width,
+        cells.height,
+        TextDecorations::strip(regular),
+        bold.map(TextDecorations::strip),
+        italic.map(TextDecorations::strip),
+    );
+    (backend, decorations)
+}
@@ -0,0 +1,224 @@
+//! Drawing of the text cursor in windowed mode.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use ratatui::{buffer::Buffer, layout::Position, style::Color};
+
+use super::palette::TerminalPalette;
+
+/// How the text cursor is drawn in windowed mode.
+///
+/// The cursor is shown where the last frame placed it with
+/// [`Frame::set_cursor_position`][ratatui::Frame::set_cursor_position], and hidden when a frame
+/// didn't place it or [`Terminal::hide_cursor`][ratatui::Terminal::hide_cursor] was called.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct WindowedCursor {
+    /// The shape of the cursor.
+    pub shape: CursorShape,
+    /// How long the cursor stays shown, then hidden, while blinking, or `None` for a steady cursor.
+    ///
+    /// The cursor is shown again whenever it moves, so it doesn't disappear while typing.
+    pub blink: Option<Duration>,
+    /// The color of the cursor, or `None` to use the foreground color of the cell under it.
+    pub color: Option<Color>,
+    /// The color of the character under a [block](CursorShape::Block) cursor, or `None` to use the
+    /// background color of the cell.
+    pub text_color: Option<Color>,
+}
+
+impl Default for WindowedCursor {
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::default(),
+            blink: Some(Duration::from_millis(530)),
+            color: None,
+            text_color: None,
+        }
+    }
+}
+
+/// See [`WindowedCursor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum CursorShape {
+    /// Covers the whole cell, drawing its character in the cell's background color.
+    #[default]
+    Block,
+    /// A line along the bottom of the cell.
+    Underline,
+    /// A line along the left of the cell.
+    Bar,
+}
+
+/// Where the cursor was drawn, and when it last moved.
+#[derive(Default, Debug)]
+pub(super) struct CursorBlink {
+    position: Option<Position>,
+    moved_at: Duration,
+    /// The cell the cursor was drawn in on the terminal's image, if any.
+    pub(super) drawn: Option<Position>,
+}
+
+impl CursorBlink {
+    /// The cell the cursor should be drawn in at time `now`, given where the terminal placed it.
+    pub(super) fn visible(
+        &mut self,
+        cursor: &WindowedCursor,
+        position: Option<Position>,
+        now: Duration,
+    ) -> Option<Position> {
+        if position != self.position {
+            self.position = position;
+            self.moved_at = now;
+        }
+        let shown = cursor.blink.is_none_or(|interval| {
+            interval.is_zero()
+                || ((now - self.moved_at).as_nanos() / interval.as_nanos()).is_multiple_of(2)
+        });
+        position.filter(|_| shown)
+    }
+}
+
+impl WindowedCursor {
+    /// Draws the cursor in `cell` of `buffer`, over the RGBA `image` it was rendered to with cells
+    /// of `cell_size` pixels. The colors of `buffer` must have been resolved to the RGB values they
+    /// were drawn with, reversed text included.
+    pub(super) fn draw(
+        &self,
+        buffer: &Buffer,
+        palette: &TerminalPalette,
+        cell: Position,
+        image: &mut [u8],
+        image_width: usize,
+        cell_size: UVec2,
+    ) {
+        let Some(buffer_cell) = buffer.cell(cell) else {
+            return;
+        };
+        let (cell_width, cell_height) = (cell_size.x as usize, cell_size.y as usize);
+        let (x0, y0) = (cell.x as usize * cell_width, cell.y as usize * cell_height);
+        let pixel = |x: usize, y: usize| (y * image_width + x) * 4;
+        // The backend has filled the cell with its background.
+        let Some(background) = image.get(pixel(x0, y0)..pixel(x0, y0) + 3) else {
+            return;
+        };
+        let background = [background[0], background[1], background[2]];
+        let color = palette.foreground(self.color.unwrap_or(buffer_cell.fg));
+        let text_color = self
+            .text_color
+            .map_or(background, |color| palette.background(color));
+
+        let (columns, rows) = match self.shape {
+            CursorShape::Block => (0..cell_width, 0..cell_height),
+            CursorShape::Underline => {
+                let thickness = (cell_height / 8).max(1);
+                (
+                    0..cell_width,
+                    cell_height.saturating_sub(thickness)..cell_height,
+                )
+            }
+            CursorShape::Bar => (0..(cell_width / 8).max(1).min(cell_width), 0..cell_height),
+        };
+        for y in rows {
+            for x in columns.clone().take(image_width.saturating_sub(x0)) {
+                let Some(rgba) = image.get_mut(pixel(x0 + x, y0 + y)..pixel(x0 + x, y0 + y) + 3)
+                else {
+                    return;
+                };
+                // The character under a block cursor is drawn in the cell's background color.
+                let value = if self.shape == CursorShape::Block && *rgba != background {
+                    text_color
+                } else {
+                    color
+                };
+                rgba.copy_from_slice(&value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::{super::attributes::resolve_colors, *};
+
+    const CELL: UVec2 = UVec2::new(2, 8);
+    const LIT: usize = 4 + 2;
+
+    /// A 2x1 terminal with a character drawn as a single lit pixel in its second cell.
+    fn image() -> Vec<u8> {
+        let mut image = vec![0; 4 * 8 * 4];
+        image[LIT * 4..LIT * 4 + 3].copy_from_slice(&[255; 3]);
+        image
+    }
+
+    fn colors(image: &[u8]) -> Vec<[u8; 3]> {
+        image
+            .chunks(4)
+            .map(|rgba| [rgba[0], rgba[1], rgba[2]])
+            .collect()
+    }
+
+    #[test]
+    fn block_cursors_invert_the_cell() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer[(1, 0)].set_fg(Color::Red);
+        let palette = TerminalPalette::default();
+        resolve_colors(&mut buffer, &palette);
+        let mut image = image();
+        let cursor = WindowedCursor::default();
+        cursor.draw(&buffer, &palette, Position::new(1, 0), &mut image, 4, CELL);
+
+        let colors = colors(&image);
+        // The first cell is untouched, the lit pixel of the second takes the background color.
+        assert_eq!(colors[LIT], [0; 3]);
+        assert_eq!(colors[LIT + 1], [205, 0, 0]);
+        assert_eq!(colors[4 * 7 + 1], [0; 3]);
+        assert_eq!(colors[4 * 7 + 2], [205, 0, 0]);
+    }
+
+    #[test]
+    fn underline_and_bar_cursors_cover_an_edge() {
+        let buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        let white = |shape| {
+            let mut image = image();
+            let cursor = WindowedCursor { shape, ..default() };
+            cursor.draw(
+                &buffer,
+                &TerminalPalette::default(),
+                Position::new(0, 0),
+                &mut image,
+                4,
+                CELL,
+            );
+            colors(&image)
+                .iter()
+                .enumerate()
+                .filter(|(i, color)| **color == [255; 3] && *i != LIT)
+                .map(|(i, _)| (i % 4, i / 4))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(white(CursorShape::Underline), [(0, 7), (1, 7)]);
+        assert_eq!(
+            white(CursorShape::Bar),
+            (0..8).map(|y| (0, y)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn cursors_blink_unless_they_moved() {
+        let cursor = WindowedCursor {
+            blink: Some(Duration::from_millis(500)),
+            ..default()
+        };
+        let position = Some(Position::new(1, 1));
+        let at = Duration::from_millis;
+        let mut blink = CursorBlink::default();
+        assert_eq!(blink.visible(&cursor, position, at(0)), position);
+        assert_eq!(blink.visible(&cursor, position, at(600)), None);
+        assert_eq!(blink.visible(&cursor, position, at(1100)), position);
+        let moved = Some(Position::new(2, 1));
+        assert_eq!(blink.visible(&cursor, moved, at(1600)), moved);
+        assert_eq!(blink.visible(&cursor, None, at(1700)), None);
+    }
+}
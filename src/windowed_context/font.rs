@@ -0,0 +1,723 @@
+//! TrueType and OpenType fonts for the windowed context.
+//!
+//! The soft backend draws text with monospace bitmap fonts. The characters terminal apps draw most
+//! of outline fonts are rasterized into a bitmap glyph atlas for each size they are used at, and
+//! the grid is recomputed from the new cell size, the same way it is when the window is resized.
+//! Other characters are rasterized the first time they are shown, by [`WideGlyphs`].
+use std::{collections::HashMap, fmt, ops::RangeInclusive, sync::Arc};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+    window::{PrimaryWindow, WindowScaleFactorChanged},
+};
+use embedded_graphics::{
+    image::ImageRaw,
+    mono_font::{DecorationDimensions, MonoFont, mapping::GlyphMapping},
+    prelude::Size as GlyphSize,
+};
+use fontdue::FontSettings;
+use ratatui::crossterm::event::Event;
+use soft_ratatui::embedded_graphics_unicodefonts::{
+    mono_8x13_atlas, mono_8x13_bold_atlas, mono_8x13_italic_atlas,
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    RatatuiContext,
+    input::{InputSet, ResizeMessage, update_terminal_size},
+};
+
+use super::{
+    scaling::{WindowedScaling, scalable_font},
+    wide_glyphs::WideGlyphs,
+    window_terminal::{WindowTerminal, WindowTerminalMessage},
+};
+
+/// Plugin that draws the windowed terminal with the fonts in [`WindowedFont`]. Added by the
+/// [`RatatuiPlugins`][crate::RatatuiPlugins] when using the windowed context.
+pub struct WindowedFontPlugin {
+    /// Whether Ctrl+= and Ctrl+- change the font size, and Ctrl+0 restores it, like in most
+    /// terminal emulators. Only has an effect with an outline font and the `keyboard` feature.
+    ///
+    /// The key presses are still sent as [`KeyMessage`][crate::event::KeyMessage]s.
+    pub zoom_keys: bool,
+}
+
+impl Default for WindowedFontPlugin {
+    fn default() -> Self {
+        Self { zoom_keys: true }
+    }
+}
+
+impl Plugin for WindowedFontPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WindowedFont>()
+            .init_resource::<WideGlyphs>()
+            .init_resource::<WindowedScaling>()
+            .add_systems(
+                PreUpdate,
+                apply_windowed_font
+                    .before(update_terminal_size)
+                    .in_set(InputSet::EmitCrossterm),
+            );
+        #[cfg(feature = "keyboard")]
+        if self.zoom_keys {
+            app.add_systems(
+                PreUpdate,
+                zoom_windowed_font
+                    .after(bevy::input::InputSystems)
+                    .before(apply_windowed_font)
+                    .in_set(InputSet::EmitCrossterm),
+            );
+        }
+    }
+
+    fn finish(&self, app: &mut App) {
+        // Registered once the asset server exists, whichever order the plugins were added in.
+        app.init_asset::<TerminalFont>()
+            .init_asset_loader::<TerminalFontLoader>();
+    }
+}
+
+/// The fonts the windowed terminal is drawn with.
+///
+/// Changing this resource redraws the terminal with the new fonts, refitting the grid to the
+/// window and sending a [`ResizeMessage`] if the number of cells changes.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::windowed::{TerminalFont, WindowedFont};
+/// fn setup(mut font: ResMut<WindowedFont>, asset_server: Res<AssetServer>) {
+///     let regular: Handle<TerminalFont> = asset_server.load("fonts/FiraMono-Regular.ttf");
+///     font.regular = Some(regular.into());
+///     font.size = 18.0;
+/// }
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct WindowedFont {
+    /// The font for regular text. `None` uses the built-in 8x13 bitmap font, which ignores the
+    /// other fields except `fallback`.
+    pub regular: Option<FontSource>,
+    /// The font for bold text. Bold text uses the regular font if `None`.
+    pub bold: Option<FontSource>,
+    /// The font for italic text. Italic text uses the regular font if `None`.
+    pub italic: Option<FontSource>,
+    /// Fonts for the characters the fonts above have no glyph for, such as emoji or CJK, tried in
+    /// order.
+    ///
+    /// Double-width characters are drawn across both of their cells, scaled down if needed. Color
+    /// emoji fonts can't be used, since only the outlines of glyphs are drawn; monochrome emoji
+    /// fonts can.
+    pub fallback: Vec<FontSource>,
+    /// The font size in pixels. The cell size follows from the regular font's line height and
+    /// advance at this size.
+    ///
+    /// The size is in logical pixels, unless the [`ScalingMode`][crate::windowed::ScalingMode]
+    /// magnifies the whole terminal.
+    pub size: f32,
+    /// The pixels the zoom keys added to `size`, or took from it. Ctrl+0 sets it back to `0.0`,
+    /// so changing `size` keeps the zoom and changes the size it is restored to.
+    pub zoom: f32,
+}
+
+impl Default for WindowedFont {
+    fn default() -> Self {
+        Self {
+            regular: None,
+            bold: None,
+            italic: None,
+            fallback: Vec::new(),
+            size: 16.0,
+            zoom: 0.0,
+        }
+    }
+}
+
+impl WindowedFont {
+    /// The smallest size the zoom keys shrink the font to.
+    pub const MIN_SIZE: f32 = 4.0;
+
+    /// The size the font is drawn at: `size` with the `zoom`, no smaller than
+    /// [`MIN_SIZE`][Self::MIN_SIZE] unless `size` is.
+    pub fn zoomed_size(&self) -> f32 {
+        (self.size + self.zoom).max(Self::MIN_SIZE.min(self.size))
+    }
+
+    /// Uses an outline font for all text at the given size.
+    pub fn new(regular: impl Into<FontSource>, size: f32) -> Self {
+        Self {
+            regular: Some(regular.into()),
+            size,
+            ..default()
+        }
+    }
+}
+
+/// Where a font of [`WindowedFont`] comes from.
+#[derive(Clone, Debug)]
+pub enum FontSource {
+    /// A font that is already loaded, e.g. from bytes embedded in the app.
+    Font(TerminalFont),
+    /// A font asset. The terminal keeps its previous font until the asset has loaded.
+    Asset(Handle<TerminalFont>),
+}
+
+impl FontSource {
+    fn get<'a>(&'a self, fonts: &'a Assets<TerminalFont>) -> Option<&'a TerminalFont> {
+        match self {
+            FontSource::Font(font) => Some(font),
+            FontSource::Asset(handle) => fonts.get(handle),
+        }
+    }
+}
+
+impl From<TerminalFont> for FontSource {
+    fn from(font: TerminalFont) -> Self {
+        FontSource::Font(font)
+    }
+}
+
+impl From<Handle<TerminalFont>> for FontSource {
+    fn from(handle: Handle<TerminalFont>) -> Self {
+        FontSource::Asset(handle)
+    }
+}
+
+/// A TrueType or OpenType font.
+///
+/// Loaded by the asset server from `.ttf` and `.otf` files, or created from bytes with
+/// [`from_bytes`][Self::from_bytes].
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct TerminalFont(pub(super) Arc<fontdue::Font>);
+
+impl TerminalFont {
+    /// Parses a TrueType or OpenType font.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FontError> {
+        let font = fontdue::Font::from_bytes(bytes, FontSettings::default()).map_err(FontError)?;
+        Ok(Self(Arc::new(font)))
+    }
+}
+
+/// The error returned when a font can't be parsed.
+#[derive(Debug)]
+pub struct FontError(&'static str);
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid font: {}", self.0)
+    }
+}
+
+impl std::error::Error for FontError {}
+
+#[derive(Default, TypePath)]
+struct TerminalFontLoader;
+
+impl AssetLoader for TerminalFontLoader {
+    type Asset = TerminalFont;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<TerminalFont> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(TerminalFont::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf"]
+    }
+}
+
+#[cfg(feature = "keyboard")]
+fn zoom_windowed_font(keys: Res<ButtonInput<KeyCode>>, font: ResMut<WindowedFont>) {
+    if font.regular.is_none() || !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let zoom = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        font.zoom + 1.0
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        (font.zoom - 1.0).max((WindowedFont::MIN_SIZE - font.size).min(font.zoom))
+    } else if keys.any_just_pressed([KeyCode::Digit0, KeyCode::Numpad0]) {
+        0.0
+    } else {
+        return;
+    };
+    font.map_unchanged(|font| &mut font.zoom).set_if_neq(zoom);
+}
+
+/// System that rebuilds the terminal's backend whenever [`WindowedFont`] changes, one of its
+/// font assets is (re)loaded, or the resolution fonts are rasterized at changes, along with those
+/// of the [`WindowTerminal`]s, and of any new one.
+#[allow(clippy::too_many_arguments)]
+fn apply_windowed_font(
+    font: Res<WindowedFont>,
+    fonts: Res<Assets<TerminalFont>>,
+    mut font_events: MessageReader<AssetEvent<TerminalFont>>,
+    mut scale_factor_changed: MessageReader<WindowScaleFactorChanged>,
+    scaling: Res<WindowedScaling>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    mut context: ResMut<RatatuiContext>,
+    mut resize: MessageWriter<ResizeMessage>,
+    mut terminals: Query<(Entity, &Window, &mut WindowTerminal), Without<PrimaryWindow>>,
+    mut terminal_resize: MessageWriter<WindowTerminalMessage>,
+    mut wide_glyphs: ResMut<WideGlyphs>,
+    mut atlases: Local<HashMap<AtlasKey, &'static AtlasFont>>,
+    mut waiting: Local<bool>,
+) -> Result {
+    // Every message is read, so none is left to trigger a rebuild next frame.
+    let reloaded = font_events
+        .read()
+        .filter(|event| matches!(event, AssetEvent::Modified { .. }))
+        .count()
+        > 0;
+    let rescaled = scale_factor_changed.read().count() > 0 || scaling.is_changed();
+    let added = terminals
+        .iter_mut()
+        .any(|(_, _, terminal)| terminal.is_added());
+    if !font.is_changed() && !*waiting && !reloaded && !rescaled && !added {
+        return Ok(());
+    }
+    let window = window.map(Single::into_inner);
+    let px = font.zoomed_size() * scaling.mode.font_scale(window);
+
+    let [regular, bold, italic] = [&font.regular, &font.bold, &font.italic]
+        .map(|source| source.as_ref().map(|source| source.get(&fonts)));
+    let fallback: Vec<_> = font
+        .fallback
+        .iter()
+        .map(|source| source.get(&fonts))
+        .collect();
+    *waiting = [regular, bold, italic]
+        .iter()
+        .any(|face| matches!(face, Some(None)))
+        || fallback.iter().any(Option::is_none);
+    if *waiting {
+        return Ok(());
+    }
+    let fallback: Vec<_> = fallback.into_iter().flatten().collect();
+
+    let regular = regular.flatten();
+    let outlined = regular.and_then(|regular| {
+        let cell = CellMetrics::new(&regular.0, px);
+        let chains = [Some(regular), bold.flatten(), italic.flatten()].map(|face| {
+            face.map(|face| {
+                let chain: Vec<_> = [face].into_iter().chain(fallback.iter().copied()).collect();
+                let key: AtlasKey = (
+                    chain.iter().map(|face| face.0.file_hash()).collect(),
+                    px.to_bits(),
+                    cell,
+                );
+                (key, chain)
+            })
+        });
+        let new = chains
+            .iter()
+            .flatten()
+            .filter(|(key, _)| !atlases.contains_key(key))
+            .count();
+        if atlases.len() + new > MAX_ATLASES {
+            tracing::warn!(
+                "{MAX_ATLASES} font atlases were rasterized already, using the built-in font"
+            );
+            return None;
+        }
+        let [regular, bold, italic] = chains.map(|chain| {
+            chain.map(|(key, chain)| {
+                atlases
+                    .entry(key)
+                    .or_insert_with(|| Box::leak(Box::new(rasterize(&chain, px, cell).finish())))
+                    .mono_font()
+            })
+        });
+        Some((regular.expect("atlas of the regular font"), bold, italic))
+    });
+    // Past the limit, the outline fonts are left out altogether.
+    let regular = regular.filter(|_| outlined.is_some());
+    let (regular_font, bold, italic) = outlined.unwrap_or_else(|| {
+        (
+            mono_8x13_atlas(),
+            Some(mono_8x13_bold_atlas()),
+            Some(mono_8x13_italic_atlas()),
+        )
+    });
+
+    // The characters left out of the atlases are drawn over the grid from the regular font or the
+    // first fallback that has them. The built-in font has no outlines and covers far more
+    // characters, so only double-width ones are drawn from the fallbacks, at the height of its
+    // cells.
+    let cell = CellMetrics {
+        width: regular_font.character_size.width,
+        height: regular_font.character_size.height,
+        baseline: regular_font.baseline,
+    };
+    let char_size = UVec2::new(cell.width, cell.height);
+    let (outlines, size) = match regular {
+        Some(regular) => (vec![regular.clone()], px),
+        None => (Vec::new(), cell.height as f32),
+    };
+    let narrow = !outlines.is_empty();
+    *wide_glyphs = WideGlyphs::new(
+        outlines
+            .into_iter()
+            .chain(fallback.into_iter().cloned())
+            .collect(),
+        size,
+        cell,
+        narrow,
+    );
+    let regular = regular_font;
+
+    let previous = context.size()?;
+    let cells = match window {
+        Some(window) => scaling
+            .mode
+            .grid_size(window, scalable_font(Some(&font)), char_size),
+        // Without a window to fit, the grid keeps its size.
+        None => previous,
+    };
+    context.set_fonts(cells, regular, bold, italic)?;
+    if cells != previous {
+        resize.write(ResizeMessage(cells));
+    }
+    for (window_entity, window, mut terminal) in &mut terminals {
+        let cells = scaling
+            .mode
+            .grid_size(window, scalable_font(Some(&font)), char_size);
+        let previous = terminal.size()?;
+        terminal.set_fonts(cells, regular, bold, italic)?;
+        if cells != previous {
+            terminal_resize.write(WindowTerminalMessage {
+                window: window_entity,
+                event: Event::Resize(cells.width, cells.height),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// How many atlases are rasterized at most. The backend borrows its fonts for `'static`, so
+/// atlases are leaked, and cached by font and size so zooming back and forth reuses them. Past
+/// this, the built-in font is drawn with instead.
+const MAX_ATLASES: usize = 64;
+
+/// Identifies a rasterized atlas: its fonts, the size in pixels and the cell it was fitted to.
+type AtlasKey = (Vec<usize>, u32, CellMetrics);
+
+/// The size of a cell in pixels, and where the baseline is within it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub(super) struct CellMetrics {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) baseline: u32,
+}
+
+impl CellMetrics {
+    /// The cell of a monospace font: as wide as its advance, as high as its lines.
+    fn new(font: &fontdue::Font, px: f32) -> Self {
+        let (ascent, height) = font
+            .horizontal_line_metrics(px)
+            .map_or((px, px), |line| (line.ascent, line.new_line_size));
+        let advance = font.metrics('M', px).advance_width;
+        Self {
+            width: advance.ceil().max(1.0) as u32,
+            height: height.ceil().max(1.0) as u32,
+            baseline: ascent.ceil().max(0.0) as u32,
+        }
+    }
+}
+
+/// The characters rasterized into the atlases of outline fonts: ASCII and Latin-1, and the
+/// punctuation, arrows, box drawing, blocks, shapes and braille that terminal apps draw with.
+const ATLAS_CHARACTERS: [RangeInclusive<char>; 7] = [
+    ' '..='~',
+    '\u{A0}'..='\u{FF}',
+    '\u{2010}'..='\u{205E}',
+    '\u{2190}'..='\u{21FF}',
+    '\u{2500}'..='\u{25FF}',
+    '\u{2800}'..='\u{28FF}',
+    '\u{FFFD}'..='\u{FFFD}',
+];
+
+/// Whether a character is drawn from the atlases of outline fonts rather than by [`WideGlyphs`].
+pub(super) fn in_atlas(character: char) -> bool {
+    ATLAS_CHARACTERS
+        .iter()
+        .any(|range| range.contains(&character))
+}
+
+/// Rasterizes the [atlas characters](ATLAS_CHARACTERS) of a chain of fonts into an atlas of
+/// `cell`-sized glyphs, taking each character from the first font that has it.
+fn rasterize(chain: &[&TerminalFont], px: f32, cell: CellMetrics) -> GlyphAtlas {
+    let characters = ATLAS_CHARACTERS.iter().cloned().flatten();
+    let mut atlas = GlyphAtlas::new(cell, characters.clone().count());
+    // Double-width characters don't fit a cell; they are drawn by `WideGlyphs`.
+    for character in characters.filter(|character| character.width() != Some(2)) {
+        let Some(font) = chain.iter().find(|font| font.0.has_glyph(character)) else {
+            continue;
+        };
+        let (metrics, coverage) = font.0.rasterize(character, px);
+        atlas.insert(character, &metrics, &coverage);
+    }
+    atlas
+}
+
+/// Glyphs laid out in a 1-bit image, in the format of [`MonoFont`]s.
+struct GlyphAtlas {
+    cell: CellMetrics,
+    /// Width of the atlas in pixels.
+    width: u32,
+    /// The pixels, one bit each, with rows padded to whole bytes.
+    data: Vec<u8>,
+    /// The index of each character's glyph.
+    indices: HashMap<char, usize>,
+}
+
+impl GlyphAtlas {
+    const GLYPHS_PER_ROW: u32 = 64;
+    /// Coverage from which a pixel of a rasterized glyph is drawn.
+    const THRESHOLD: u8 = 128;
+
+    fn new(cell: CellMetrics, capacity: usize) -> Self {
+        let width = cell.width * Self::GLYPHS_PER_ROW;
+        let rows = (capacity as u32).div_ceil(Self::GLYPHS_PER_ROW).max(1);
+        Self {
+            cell,
+            width,
+            data: vec![0; width.div_ceil(8) as usize * (rows * cell.height) as usize],
+            indices: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn contains(&self, character: char) -> bool {
+        self.indices.contains_key(&character)
+    }
+
+    /// Adds a glyph, aligning its bitmap to the cell's baseline and clipping it to the cell.
+    fn insert(&mut self, character: char, metrics: &fontdue::Metrics, coverage: &[u8]) {
+        let index = self.indices.len();
+        self.indices.insert(character, index);
+        let row_bytes = self.width.div_ceil(8) as usize;
+        let glyph_rows = (index as u32 / Self::GLYPHS_PER_ROW + 1) * self.cell.height;
+        self.data
+            .resize(self.data.len().max(row_bytes * glyph_rows as usize), 0);
+
+        let origin_x = (index as u32 % Self::GLYPHS_PER_ROW * self.cell.width) as i32;
+        let origin_y = (index as u32 / Self::GLYPHS_PER_ROW * self.cell.height) as i32;
+        // Bitmaps are stored top row first, and `ymin` is the offset of their bottom edge from
+        // the baseline, upwards.
+        let top = self.cell.baseline as i32 - metrics.ymin - metrics.height as i32;
+        for (i, &value) in coverage.iter().enumerate() {
+            let x = metrics.xmin + (i % metrics.width) as i32;
+            let y = top + (i / metrics.width) as i32;
+            let inside = (0..self.cell.width as i32).contains(&x)
+                && (0..self.cell.height as i32).contains(&y);
+            if value >= Self::THRESHOLD && inside {
+                let (x, y) = ((origin_x + x) as usize, (origin_y + y) as usize);
+                self.data[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    /// Finishes the atlas, to be drawn with by the backend.
+    fn finish(mut self) -> AtlasFont {
+        if !self.contains(' ') {
+            self.insert(' ', &fontdue::Metrics::default(), &[]);
+        }
+        let blank = self.indices[&' '];
+        let replacement = ['\u{FFFD}', '?']
+            .iter()
+            .find_map(|character| self.indices.get(character).copied())
+            .unwrap_or(blank);
+        AtlasFont {
+            cell: self.cell,
+            width: self.width,
+            data: self.data,
+            mapping: AtlasMapping {
+                indices: self.indices,
+                replacement,
+                blank,
+            },
+        }
+    }
+}
+
+/// A finished [`GlyphAtlas`].
+struct AtlasFont {
+    cell: CellMetrics,
+    width: u32,
+    data: Vec<u8>,
+    mapping: AtlasMapping,
+}
+
+impl AtlasFont {
+    fn mono_font(&self) -> MonoFont<'_> {
+        let cell = self.cell;
+        MonoFont {
+            image: ImageRaw::new(&self.data, self.width),
+            glyph_mapping: &self.mapping,
+            character_size: GlyphSize::new(cell.width, cell.height),
+            character_spacing: 0,
+            baseline: cell.baseline.min(cell.height - 1),
+            underline: DecorationDimensions::new((cell.baseline + 1).min(cell.height - 1), 1),
+            strikethrough: DecorationDimensions::new(cell.baseline * 2 / 3, 1),
+        }
+    }
+}
+
+/// Maps characters to their glyph in a [`GlyphAtlas`], drawing the atlas characters the fonts have
+/// no glyph for with a replacement glyph, and leaving the cells of other characters blank for
+/// [`WideGlyphs`] to draw.
+struct AtlasMapping {
+    indices: HashMap<char, usize>,
+    replacement: usize,
+    blank: usize,
+}
+
+impl GlyphMapping for AtlasMapping {
+    fn index(&self, character: char) -> usize {
+        match self.indices.get(&character) {
+            Some(&index) => index,
+            None if in_atlas(character) => self.replacement,
+            None => self.blank,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::{
+        image::GetPixel,
+        pixelcolor::BinaryColor,
+        prelude::{OriginDimensions, Point},
+    };
+
+    use super::*;
+
+    const CELL: CellMetrics = CellMetrics {
+        width: 4,
+        height: 6,
+        baseline: 4,
+    };
+
+    /// A solid glyph of the given size whose bottom edge is `ymin` above the baseline.
+    fn glyph(xmin: i32, ymin: i32, width: usize, height: usize) -> (fontdue::Metrics, Vec<u8>) {
+        let metrics = fontdue::Metrics {
+            xmin,
+            ymin,
+            width,
+            height,
+            ..default()
+        };
+        (metrics, vec![255; width * height])
+    }
+
+    fn lit(font: &MonoFont, index: usize) -> Vec<(i32, i32)> {
+        let per_row = font.image.size().width / CELL.width;
+        let origin = Point::new(
+            (index as u32 % per_row * CELL.width) as i32,
+            (index as u32 / per_row * CELL.height) as i32,
+        );
+        let mut pixels = Vec::new();
+        for y in 0..CELL.height as i32 {
+            for x in 0..CELL.width as i32 {
+                if font.image.pixel(origin + Point::new(x, y)) == Some(BinaryColor::On) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn glyphs_sit_on_the_baseline() {
+        let mut atlas = GlyphAtlas::new(CELL, 2);
+        let (metrics, coverage) = glyph(1, 0, 2, 2);
+        atlas.insert('a', &metrics, &coverage);
+        let (metrics, coverage) = glyph(0, -1, 1, 2);
+        atlas.insert('b', &metrics, &coverage);
+        let atlas = atlas.finish();
+        let font = atlas.mono_font();
+
+        assert_eq!(font.glyph_mapping.index('a'), 0);
+        assert_eq!(lit(&font, 0), [(1, 2), (2, 2), (1, 3), (2, 3)]);
+        // Descenders go below the baseline.
+        assert_eq!(font.glyph_mapping.index('b'), 1);
+        assert_eq!(lit(&font, 1), [(0, 3), (0, 4)]);
+    }
+
+    #[test]
+    fn glyphs_are_clipped_to_their_cell() {
+        let mut atlas = GlyphAtlas::new(CELL, 2);
+        let (metrics, coverage) = glyph(-1, -3, 6, 8);
+        atlas.insert('a', &metrics, &coverage);
+        atlas.insert('b', &fontdue::Metrics::default(), &[]);
+        let atlas = atlas.finish();
+        let font = atlas.mono_font();
+
+        assert_eq!(lit(&font, 0).len(), (CELL.width * CELL.height) as usize);
+        assert!(lit(&font, 1).is_empty());
+    }
+
+    #[test]
+    fn glyphs_wrap_onto_new_rows() {
+        let count = GlyphAtlas::GLYPHS_PER_ROW as usize + 1;
+        let mut atlas = GlyphAtlas::new(CELL, 1);
+        for index in 0..count {
+            let character = char::from_u32('a' as u32 + index as u32).unwrap();
+            let (metrics, coverage) = glyph(0, 0, 1, 1);
+            atlas.insert(character, &metrics, &coverage);
+        }
+        let atlas = atlas.finish();
+        let font = atlas.mono_font();
+
+        assert_eq!(font.image.size().height, CELL.height * 2);
+        assert_eq!(lit(&font, count - 1), [(0, 3)]);
+    }
+
+    #[test]
+    fn missing_atlas_characters_use_the_replacement_glyph() {
+        let mut atlas = GlyphAtlas::new(CELL, 2);
+        atlas.insert('a', &fontdue::Metrics::default(), &[]);
+        atlas.insert('?', &fontdue::Metrics::default(), &[]);
+        let atlas = atlas.finish();
+        let font = atlas.mono_font();
+
+        assert_eq!(font.glyph_mapping.index('a'), 0);
+        assert_eq!(font.glyph_mapping.index('─'), 1);
+        // Left blank for `WideGlyphs` to draw.
+        assert_eq!(
+            font.glyph_mapping.index('日'),
+            font.glyph_mapping.index(' ')
+        );
+    }
+
+    #[cfg(feature = "keyboard")]
+    #[test]
+    fn resetting_the_zoom_restores_the_size_set_last() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(WindowedFont::new(Handle::<TerminalFont>::default(), 12.0))
+            .add_systems(Update, zoom_windowed_font);
+        let press = |app: &mut App, key: KeyCode| {
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.reset_all();
+            keys.press(KeyCode::ControlLeft);
+            keys.press(key);
+            app.update();
+        };
+        press(&mut app, KeyCode::Equal);
+        press(&mut app, KeyCode::Equal);
+        app.world_mut().resource_mut::<WindowedFont>().size = 20.0;
+        assert_eq!(app.world().resource::<WindowedFont>().zoomed_size(), 22.0);
+
+        press(&mut app, KeyCode::Digit0);
+        assert_eq!(app.world().resource::<WindowedFont>().zoomed_size(), 20.0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Sends the input messages in windowed mode.
+//!
+//! Bevy's window input is read back into [`KeyMessage`][crate::event::KeyMessage]s,
+//! [`MouseMessage`][crate::event::MouseMessage]s, [`ResizeMessage`][crate::event::ResizeMessage]s
+//! and [`FocusMessage`]s, so the systems that handle terminal input work unchanged with the
+//! windowed context.
+//!
+//! Keys are reported like a terminal using the full kitty keyboard protocol would report them,
+//! with releases, repeats and presses of the modifier keys themselves.
+//!
+//! Input from a window with a [`WindowTerminal`] is sent as [`WindowTerminalMessage`]s instead.
+#[cfg(any(feature = "keyboard", feature = "mouse"))]
+use bevy::input::InputSystems;
+#[cfg(feature = "mouse")]
+use bevy::{
+    ecs::entity::{EntityHashMap, EntityHashSet},
+    input::{
+        ButtonState,
+        mouse::{MouseButtonInput, MouseWheel},
+    },
+    window::{CursorMoved, PrimaryWindow},
+};
+use bevy::{prelude::*, window::WindowFocused};
+use ratatui::crossterm::event::Event;
+#[cfg(feature = "mouse")]
+use ratatui::{
+    crossterm::event::{MouseButton as CrosstermMouseButton, MouseEvent, MouseEventKind},
+    layout::Position,
+};
+
+use crate::input::{FocusMessage, InputMessagePlugin, InputSet};
+#[cfg(feature = "mouse")]
+use crate::{RatatuiContext, input::MouseMessage};
+
+#[cfg(all(feature = "keyboard", feature = "mouse"))]
+use super::keyboard_translation::held_modifiers;
+#[cfg(feature = "keyboard")]
+use super::keyboard_translation::send_key_messages;
+#[cfg(feature = "mouse")]
+use super::viewport::{TerminalPointer, TerminalViewport};
+use super::window_terminal::{WindowTerminal, WindowTerminalMessage};
+
+/// Plugin that sends the [input messages][crate::event] from the window's input.
+pub struct WindowedInputPlugin;
+
+impl Plugin for WindowedInputPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<InputMessagePlugin>() {
+            app.add_plugins(InputMessagePlugin);
+        }
+        app.add_message::<WindowTerminalMessage>().add_systems(
+            PreUpdate,
+            send_focus_messages.in_set(InputSet::EmitCrossterm),
+        );
+        // Bevy's button state has to be up to date for the modifiers and held mouse buttons.
+        #[cfg(feature = "keyboard")]
+        app.add_systems(
+            PreUpdate,
+            send_key_messages
+                .after(InputSystems)
+                .in_set(InputSet::EmitCrossterm),
+        );
+        #[cfg(feature = "mouse")]
+        app.add_systems(
+            PreUpdate,
+            (send_mouse_messages, send_window_terminal_mouse)
+                .after(InputSystems)
+                .in_set(InputSet::EmitCrossterm),
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[cfg(feature = "mouse")]
+fn send_mouse_messages(
+    window: Single<&Window, With<PrimaryWindow>>,
+    viewport: Res<TerminalViewport>,
+    pointer: Res<TerminalPointer>,
+    context: Res<RatatuiContext>,
+    terminals: Query<(), With<WindowTerminal>>,
+    #[cfg(feature = "keyboard")] keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor_moved: MessageReader<CursorMoved>,
+    mut button_input: MessageReader<MouseButtonInput>,
+    mut mouse_wheel: MessageReader<MouseWheel>,
+    mut mouse: MessageWriter<MouseMessage>,
+    mut last_cell: Local<Option<Position>>,
+) {
+    // Input from windows with terminals of their own is theirs. Every message is read, so none are
+    // left over to be reported as motion next frame.
+    let cursor_moved = cursor_moved
+        .read()
+        .filter(|moved| !terminals.contains(moved.window))
+        .count()
+        > 0;
+    let (moved, hit) = match *pointer {
+        TerminalPointer::Window => (
+            cursor_moved,
+            window.cursor_position().and_then(|position| {
+                let cell = viewport.cell_at(position)?;
+                Some(((position - viewport.rect.min).as_uvec2(), cell))
+            }),
+        ),
+        TerminalPointer::Uv(uv) => (
+            pointer.is_changed(),
+            uv.and_then(|uv| {
+                let cell = viewport.cell_at_uv(uv)?;
+                let backend = context.backend();
+                let image = Vec2::new(
+                    backend.get_pixmap_width() as f32,
+                    backend.get_pixmap_height() as f32,
+                );
+                Some(((uv * image).as_uvec2(), cell))
+            }),
+        ),
+    };
+    let Some((position, cell)) = hit else {
+        // Input outside the terminal has no cell to be reported at.
+        button_input.clear();
+        mouse_wheel.clear();
+        *last_cell = None;
+        return;
+    };
+    #[cfg(feature = "keyboard")]
+    let modifiers = held_modifiers(&keys);
+    #[cfg(not(feature = "keyboard"))]
+    let modifiers = ratatui::crossterm::event::KeyModifiers::empty();
+    let mut send = |kind| {
+        mouse.write(MouseMessage {
+            event: MouseEvent {
+                kind,
+                column: cell.x,
+                row: cell.y,
+                modifiers,
+            },
+            pixel_position: Some(position),
+        });
+    };
+
+    // Like a terminal, only report motion from one cell to another.
+    if moved && *last_cell != Some(cell) {
+        send(motion_kind(&buttons));
+    }
+    *last_cell = Some(cell);
+
+    let own = |window: Entity| !terminals.contains(window);
+    button_input
+        .read()
+        .filter(|input| own(input.window))
+        .filter_map(button_kind)
+        .for_each(&mut send);
+    mouse_wheel
+        .read()
+        .filter(|wheel| own(wheel.window))
+        .flat_map(wheel_kinds)
+        .for_each(send);
+}
+
+/// Sends the mouse input of each window with a [`WindowTerminal`] as [`WindowTerminalMessage`]s,
+/// at the cells of its terminal.
+#[cfg(feature = "mouse")]
+#[allow(clippy::too_many_arguments)]
+fn send_window_terminal_mouse(
+    terminals: Query<(Entity, &Window, &WindowTerminal)>,
+    #[cfg(feature = "keyboard")] keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor_moved: MessageReader<CursorMoved>,
+    mut button_input: MessageReader<MouseButtonInput>,
+    mut mouse_wheel: MessageReader<MouseWheel>,
+    mut messages: MessageWriter<WindowTerminalMessage>,
+    mut last_cells: Local<EntityHashMap<Position>>,
+) {
+    let moved: EntityHashSet = cursor_moved.read().map(|moved| moved.window).collect();
+    let button_input: Vec<_> = button_input.read().collect();
+    let mouse_wheel: Vec<_> = mouse_wheel.read().collect();
+    #[cfg(feature = "keyboard")]
+    let modifiers = held_modifiers(&keys);
+    #[cfg(not(feature = "keyboard"))]
+    let modifiers = ratatui::crossterm::event::KeyModifiers::empty();
+
+    for (window_entity, window, terminal) in &terminals {
+        let Some(cell) = window
+            .cursor_position()
+            .and_then(|position| terminal.viewport().cell_at(position))
+        else {
+            last_cells.remove(&window_entity);
+            continue;
+        };
+        let mut send = |kind| {
+            messages.write(WindowTerminalMessage {
+                window: window_entity,
+                event: Event::Mouse(MouseEvent {
+                    kind,
+                    column: cell.x,
+                    row: cell.y,
+                    modifiers,
+                }),
+            });
+        };
+        if moved.contains(&window_entity) && last_cells.get(&window_entity) != Some(&cell) {
+            send(motion_kind(&buttons));
+        }
+        last_cells.insert(window_entity, cell);
+
+        button_input
+            .iter()
+            .filter(|input| input.window == window_entity)
+            .filter_map(|input| button_kind(input))
+            .for_each(&mut send);
+        mouse_wheel
+            .iter()
+            .filter(|wheel| wheel.window == window_entity)
+            .flat_map(|wheel| wheel_kinds(wheel))
+            .for_each(send);
+    }
+}
+
+/// A move, or a drag while a button is held.
+#[cfg(feature = "mouse")]
+fn motion_kind(buttons: &ButtonInput<MouseButton>) -> MouseEventKind {
+    let held = buttons.get_pressed().find_map(to_crossterm_button);
+    held.map_or(MouseEventKind::Moved, MouseEventKind::Drag)
+}
+
+#[cfg(feature = "mouse")]
+fn button_kind(input: &MouseButtonInput) -> Option<MouseEventKind> {
+    let button = to_crossterm_button(&input.button)?;
+    Some(match input.state {
+        ButtonState::Pressed => MouseEventKind::Down(button),
+        ButtonState::Released => MouseEventKind::Up(button),
+    })
+}
+
+/// The reverse of the scroll mapping used when forwarding terminal input to bevy.
+#[cfg(feature = "mouse")]
+fn wheel_kinds(wheel: &MouseWheel) -> impl Iterator<Item = MouseEventKind> {
+    let vertical = match wheel.y {
+        y if y > 0.0 => Some(MouseEventKind::ScrollUp),
+        y if y < 0.0 => Some(MouseEventKind::ScrollDown),
+        _ => None,
+    };
+    let horizontal = match wheel.x {
+        x if x > 0.0 => Some(MouseEventKind::ScrollLeft),
+        x if x < 0.0 => Some(MouseEventKind::ScrollRight),
+        _ => None,
+    };
+    vertical.into_iter().chain(horizontal)
+}
+
+#[cfg(feature = "mouse")]
+fn to_crossterm_button(button: &MouseButton) -> Option<CrosstermMouseButton> {
+    match button {
+        MouseButton::Left => Some(CrosstermMouseButton::Left),
+        MouseButton::Right => Some(CrosstermMouseButton::Right),
+        MouseButton::Middle => Some(CrosstermMouseButton::Middle),
+        _ => None,
+    }
+}
+
+fn send_focus_messages(
+    mut window_focused: MessageReader<WindowFocused>,
+    terminals: Query<(), With<WindowTerminal>>,
+    mut focus: MessageWriter<FocusMessage>,
+    mut terminal_focus: MessageWriter<WindowTerminalMessage>,
+) {
+    for message in window_focused.read() {
+        if terminals.contains(message.window) {
+            terminal_focus.write(WindowTerminalMessage {
+                window: message.window,
+                event: if message.focused {
+                    Event::FocusGained
+                } else {
+                    Event::FocusLost
+                },
+            });
+            continue;
+        }
+        focus.write(if message.focused {
+            FocusMessage::Gained
+        } else {
+            FocusMessage::Lost
+        });
+    }
+}
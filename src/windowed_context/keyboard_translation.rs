@@ -0,0 +1,322 @@
+//! Input translation for the keyboard in windowed mode
+//!
+//! This is the reverse of the crossterm context's keyboard forwarding: bevy [`KeyboardInput`]s are
+//! sent as [`KeyMessage`]s, so apps written against crossterm's key events run unchanged in a
+//! window. Unlike most terminals, a window always reports key releases and repeats, and reports
+//! modifier keys on their own, like a terminal using the full kitty protocol.
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use ratatui::crossterm::event::{
+    Event, KeyCode as CrosstermKeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    ModifierKeyCode,
+};
+
+use crate::input::KeyMessage;
+
+use super::window_terminal::{WindowTerminal, WindowTerminalMessage};
+
+/// System that sends a [`KeyMessage`] for each [`KeyboardInput`] crossterm has a key for, or a
+/// [`WindowTerminalMessage`] for those of a window with a [`WindowTerminal`].
+pub(super) fn send_key_messages(
+    mut keyboard: MessageReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    terminals: Query<(), With<WindowTerminal>>,
+    mut messages: MessageWriter<KeyMessage>,
+    mut terminal_messages: MessageWriter<WindowTerminalMessage>,
+) {
+    let modifiers = held_modifiers(&keys);
+    for input in keyboard.read() {
+        let Some(event) = to_key_event(input, modifiers) else {
+            continue;
+        };
+        if terminals.contains(input.window) {
+            terminal_messages.write(WindowTerminalMessage {
+                window: input.window,
+                event: Event::Key(event),
+            });
+        } else {
+            messages.write(KeyMessage(event));
+        }
+    }
+}
+
+/// The crossterm modifiers for the modifier keys currently held.
+pub(super) fn held_modifiers(keys: &ButtonInput<KeyCode>) -> KeyModifiers {
+    use KeyCode as b;
+    let mut modifiers = KeyModifiers::empty();
+    for (codes, modifier) in [
+        ([b::ShiftLeft, b::ShiftRight], KeyModifiers::SHIFT),
+        ([b::ControlLeft, b::ControlRight], KeyModifiers::CONTROL),
+        ([b::AltLeft, b::AltRight], KeyModifiers::ALT),
+        ([b::SuperLeft, b::SuperRight], KeyModifiers::SUPER),
+        ([b::Hyper, b::Hyper], KeyModifiers::HYPER),
+        ([b::Meta, b::Meta], KeyModifiers::META),
+    ] {
+        if keys.any_pressed(codes) {
+            modifiers |= modifier;
+        }
+    }
+    modifiers
+}
+
+/// Converts a bevy key input to a crossterm key event, if crossterm has a key for it.
+fn to_key_event(input: &KeyboardInput, modifiers: KeyModifiers) -> Option<KeyEvent> {
+    let kind = match (input.state, input.repeat) {
+        (ButtonState::Pressed, false) => KeyEventKind::Press,
+        (ButtonState::Pressed, true) => KeyEventKind::Repeat,
+        (ButtonState::Released, _) => KeyEventKind::Release,
+    };
+    let code = match modifier_keycode(&input.key_code, &input.logical_key) {
+        Some(modifier) => CrosstermKeyCode::Modifier(modifier),
+        None => to_crossterm_keycode(&input.logical_key)?,
+    };
+    // Terminals report shift+tab as its own key.
+    let code = match code {
+        CrosstermKeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+            CrosstermKeyCode::BackTab
+        }
+        code => code,
+    };
+    let state = if is_keypad(&input.key_code) {
+        KeyEventState::KEYPAD
+    } else {
+        KeyEventState::NONE
+    };
+    Some(KeyEvent {
+        code,
+        modifiers,
+        kind,
+        state,
+    })
+}
+
+/// Modifier keys are told apart by their physical key, since the logical key does not say which
+/// side of the keyboard they are on.
+fn modifier_keycode(key_code: &KeyCode, logical_key: &Key) -> Option<ModifierKeyCode> {
+    use KeyCode as b;
+    use ModifierKeyCode::*;
+    if *logical_key == Key::AltGraph {
+        return Some(IsoLevel3Shift);
+    }
+    match key_code {
+        b::ShiftLeft => Some(LeftShift),
+        b::ControlLeft => Some(LeftControl),
+        b::AltLeft => Some(LeftAlt),
+        b::SuperLeft => Some(LeftSuper),
+        b::Hyper => Some(LeftHyper),
+        b::Meta => Some(LeftMeta),
+        b::ShiftRight => Some(RightShift),
+        b::ControlRight => Some(RightControl),
+        b::AltRight => Some(RightAlt),
+        b::SuperRight => Some(RightSuper),
+        _ => None,
+    }
+}
+
+fn to_crossterm_keycode(logical_key: &Key) -> Option<CrosstermKeyCode> {
+    use CrosstermKeyCode as c;
+    use Key as b;
+    use ratatui::crossterm::event::MediaKeyCode as m;
+    match logical_key {
+        b::Character(text) => {
+            // Dead-key sequences can produce more than one character; crossterm has no key for those.
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(character), None) => Some(c::Char(character)),
+                _ => None,
+            }
+        }
+        b::Space => Some(c::Char(' ')),
+        b::Backspace => Some(c::Backspace),
+        b::Enter => Some(c::Enter),
+        b::ArrowLeft => Some(c::Left),
+        b::ArrowRight => Some(c::Right),
+        b::ArrowUp => Some(c::Up),
+        b::ArrowDown => Some(c::Down),
+        b::Home => Some(c::Home),
+        b::End => Some(c::End),
+        b::PageUp => Some(c::PageUp),
+        b::PageDown => Some(c::PageDown),
+        b::Tab => Some(c::Tab),
+        b::Delete => Some(c::Delete),
+        b::Insert => Some(c::Insert),
+        b::F1 => Some(c::F(1)),
+        b::F2 => Some(c::F(2)),
+        b::F3 => Some(c::F(3)),
+        b::F4 => Some(c::F(4)),
+        b::F5 => Some(c::F(5)),
+        b::F6 => Some(c::F(6)),
+        b::F7 => Some(c::F(7)),
+        b::F8 => Some(c::F(8)),
+        b::F9 => Some(c::F(9)),
+        b::F10 => Some(c::F(10)),
+        b::F11 => Some(c::F(11)),
+        b::F12 => Some(c::F(12)),
+        b::F13 => Some(c::F(13)),
+        b::F14 => Some(c::F(14)),
+        b::F15 => Some(c::F(15)),
+        b::F16 => Some(c::F(16)),
+        b::F17 => Some(c::F(17)),
+        b::F18 => Some(c::F(18)),
+        b::F19 => Some(c::F(19)),
+        b::F20 => Some(c::F(20)),
+        b::F21 => Some(c::F(21)),
+        b::F22 => Some(c::F(22)),
+        b::F23 => Some(c::F(23)),
+        b::F24 => Some(c::F(24)),
+        b::F25 => Some(c::F(25)),
+        b::F26 => Some(c::F(26)),
+        b::F27 => Some(c::F(27)),
+        b::F28 => Some(c::F(28)),
+        b::F29 => Some(c::F(29)),
+        b::F30 => Some(c::F(30)),
+        b::F31 => Some(c::F(31)),
+        b::F32 => Some(c::F(32)),
+        b::F33 => Some(c::F(33)),
+        b::F34 => Some(c::F(34)),
+        b::F35 => Some(c::F(35)),
+        b::Escape => Some(c::Esc),
+        b::CapsLock => Some(c::CapsLock),
+        b::ScrollLock => Some(c::ScrollLock),
+        b::NumLock => Some(c::NumLock),
+        b::PrintScreen => Some(c::PrintScreen),
+        b::Pause => Some(c::Pause),
+        b::ContextMenu => Some(c::Menu),
+        b::MediaPlay => Some(c::Media(m::Play)),
+        b::MediaPause => Some(c::Media(m::Pause)),
+        b::MediaPlayPause => Some(c::Media(m::PlayPause)),
+        b::MediaStop => Some(c::Media(m::Stop)),
+        b::MediaFastForward => Some(c::Media(m::FastForward)),
+        b::MediaRewind => Some(c::Media(m::Rewind)),
+        b::MediaTrackNext => Some(c::Media(m::TrackNext)),
+        b::MediaTrackPrevious => Some(c::Media(m::TrackPrevious)),
+        b::MediaRecord => Some(c::Media(m::Record)),
+        b::AudioVolumeDown => Some(c::Media(m::LowerVolume)),
+        b::AudioVolumeUp => Some(c::Media(m::RaiseVolume)),
+        b::AudioVolumeMute => Some(c::Media(m::MuteVolume)),
+        _ => None,
+    }
+}
+
+fn is_keypad(key_code: &KeyCode) -> bool {
+    use KeyCode as b;
+    matches!(
+        key_code,
+        b::Numpad0
+            | b::Numpad1
+            | b::Numpad2
+            | b::Numpad3
+            | b::Numpad4
+            | b::Numpad5
+            | b::Numpad6
+            | b::Numpad7
+            | b::Numpad8
+            | b::Numpad9
+            | b::NumpadAdd
+            | b::NumpadComma
+            | b::NumpadDecimal
+            | b::NumpadDivide
+            | b::NumpadEnter
+            | b::NumpadEqual
+            | b::NumpadMultiply
+            | b::NumpadSubtract
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(
+        key_code: KeyCode,
+        logical_key: Key,
+        state: ButtonState,
+        repeat: bool,
+    ) -> KeyboardInput {
+        KeyboardInput {
+            key_code,
+            logical_key,
+            state,
+            text: None,
+            repeat,
+            window: Entity::PLACEHOLDER,
+        }
+    }
+
+    #[test]
+    fn presses_repeats_and_releases_keep_their_kind() {
+        let press = input(
+            KeyCode::KeyA,
+            Key::Character("a".into()),
+            ButtonState::Pressed,
+            false,
+        );
+        let repeat = KeyboardInput {
+            repeat: true,
+            ..press.clone()
+        };
+        let release = KeyboardInput {
+            state: ButtonState::Released,
+            ..press.clone()
+        };
+        for (input, kind) in [
+            (press, KeyEventKind::Press),
+            (repeat, KeyEventKind::Repeat),
+            (release, KeyEventKind::Release),
+        ] {
+            assert_eq!(
+                to_key_event(&input, KeyModifiers::NONE),
+                Some(KeyEvent::new_with_kind(
+                    CrosstermKeyCode::Char('a'),
+                    KeyModifiers::NONE,
+                    kind
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn modifier_keys_are_reported_by_side() {
+        let event = to_key_event(
+            &input(KeyCode::ShiftRight, Key::Shift, ButtonState::Pressed, false),
+            KeyModifiers::SHIFT,
+        )
+        .unwrap();
+        assert_eq!(
+            event.code,
+            CrosstermKeyCode::Modifier(ModifierKeyCode::RightShift)
+        );
+        assert_eq!(event.modifiers, KeyModifiers::SHIFT);
+    }
+
+    #[test]
+    fn shift_tab_is_back_tab() {
+        let event = to_key_event(
+            &input(KeyCode::Tab, Key::Tab, ButtonState::Pressed, false),
+            KeyModifiers::SHIFT,
+        )
+        .unwrap();
+        assert_eq!(event.code, CrosstermKeyCode::BackTab);
+    }
+
+    #[test]
+    fn numpad_keys_are_flagged_as_keypad() {
+        let event = to_key_event(
+            &input(
+                KeyCode::Numpad7,
+                Key::Character("7".into()),
+                ButtonState::Pressed,
+                false,
+            ),
+            KeyModifiers::NONE,
+        )
+        .unwrap();
+        assert_eq!(event.code, CrosstermKeyCode::Char('7'));
+        assert_eq!(event.state, KeyEventState::KEYPAD);
+    }
+}
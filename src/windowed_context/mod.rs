@@ -1,2 +1,20 @@
+pub mod accessibility;
+mod attributes;
+pub mod clipboard;
 pub mod context;
+pub mod cursor;
+pub mod font;
+pub mod input;
+#[cfg(feature = "keyboard")]
+mod keyboard_translation;
+pub mod overlay;
+pub mod palette;
 pub mod plugin;
+#[cfg(feature = "recording")]
+pub mod recording;
+pub mod scaling;
+pub mod screenshot;
+pub mod texture;
+pub mod viewport;
+mod wide_glyphs;
+pub mod window_terminal;
@@ -0,0 +1,215 @@
+//! Bevy sprites and images placed over cells of the terminal in windowed mode.
+use bevy::{camera::visibility::RenderLayers, prelude::*};
+use ratatui::layout;
+
+use super::viewport::TerminalViewport;
+
+/// Keeps a sprite or a UI image over an area of cells of the terminal in windowed mode, e.g. tiles
+/// or a portrait in a region of a ratatui layout, following the terminal as it is scaled, resized
+/// or moved.
+///
+/// An entity with a [`Node`], such as an [`ImageNode`], should be a root UI node: it is
+/// positioned absolutely over the cells, using the [`TerminalViewport`]. An entity with a
+/// [`Sprite`] is placed in the world through the camera marked with [`IsDefaultUiCamera`], keeping
+/// its `z`, and sized with its [`custom_size`][Sprite::custom_size].
+///
+/// Overlays [below the text](OverlayLayer::BelowText) show through the pixels of their cells that
+/// have the [default background](super::palette::TerminalPalette::background), so text stays
+/// readable over them.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellOverlay {
+    /// The cells covered, in the terminal's coordinates.
+    pub area: layout::Rect,
+    /// Whether the overlay is drawn above or below the text.
+    pub layer: OverlayLayer,
+}
+
+impl CellOverlay {
+    /// The render layer of the camera that draws sprites [above the
+    /// text](OverlayLayer::AboveText), after the UI.
+    ///
+    /// [`WindowedPlugin`][super::plugin::WindowedPlugin] spawns that camera along with its
+    /// display. Apps that display the terminal themselves need a camera of their own on this layer
+    /// for such sprites to be drawn.
+    pub const RENDER_LAYER: usize = 1;
+
+    /// An overlay above the text of `area`.
+    pub fn above(area: layout::Rect) -> Self {
+        Self {
+            area,
+            layer: OverlayLayer::AboveText,
+        }
+    }
+
+    /// An overlay below the text of `area`.
+    pub fn below(area: layout::Rect) -> Self {
+        Self {
+            area,
+            layer: OverlayLayer::BelowText,
+        }
+    }
+}
+
+/// See [`CellOverlay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum OverlayLayer {
+    /// Drawn over the terminal, hiding the text of its cells.
+    #[default]
+    AboveText,
+    /// Drawn under the terminal, with the text of its cells over it.
+    BelowText,
+}
+
+impl OverlayLayer {
+    /// The global z-index of UI nodes in this layer, the terminal's own nodes having none.
+    fn z_index(self) -> GlobalZIndex {
+        match self {
+            OverlayLayer::AboveText => GlobalZIndex(1),
+            OverlayLayer::BelowText => GlobalZIndex(-1),
+        }
+    }
+
+    /// The render layers of sprites in this layer.
+    fn render_layers(self) -> RenderLayers {
+        match self {
+            OverlayLayer::AboveText => RenderLayers::layer(CellOverlay::RENDER_LAYER),
+            OverlayLayer::BelowText => RenderLayers::default(),
+        }
+    }
+}
+
+/// The areas of cells under [`CellOverlay`]s below the text, whose background is left transparent
+/// on the terminal's image.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq, Deref)]
+pub(super) struct TextUnderlays(Vec<layout::Rect>);
+
+impl TextUnderlays {
+    /// Makes the pixels of the given rows of the RGBA `image` transparent where they are under an
+    /// overlay and have the `background` color, and opaque everywhere else.
+    pub(super) fn cut_out(
+        &self,
+        rows: &[u16],
+        image: &mut [u8],
+        image_width: usize,
+        cell_size: UVec2,
+        background: [u8; 3],
+    ) {
+        let (cell_width, cell_height) = (cell_size.x as usize, cell_size.y as usize);
+        for &row in rows {
+            let start = row as usize * cell_height * image_width * 4;
+            let end = (start + cell_height * image_width * 4).min(image.len());
+            let Some(pixels) = image.get_mut(start..end) else {
+                continue;
+            };
+            for (i, rgba) in pixels.chunks_exact_mut(4).enumerate() {
+                let column = (i % image_width / cell_width.max(1)) as u16;
+                let under = self
+                    .iter()
+                    .any(|area| area.contains(layout::Position::new(column, row)));
+                rgba[3] = if under && rgba[..3] == background {
+                    0
+                } else {
+                    255
+                };
+            }
+        }
+    }
+}
+
+/// System that positions and orders the [`CellOverlay`]s over their cells.
+#[allow(clippy::type_complexity)]
+pub(super) fn place_cell_overlays(
+    mut commands: Commands,
+    viewport: Res<TerminalViewport>,
+    ui_scale: Res<UiScale>,
+    camera: Option<Single<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>>,
+    mut nodes: Query<(Entity, &CellOverlay, &mut Node, Option<&GlobalZIndex>)>,
+    mut sprites: Query<
+        (
+            Entity,
+            &CellOverlay,
+            &mut Sprite,
+            &mut Transform,
+            Option<&RenderLayers>,
+        ),
+        Without<Node>,
+    >,
+    mut underlays: ResMut<TextUnderlays>,
+) {
+    let below = nodes
+        .iter()
+        .map(|(_, overlay, ..)| overlay)
+        .chain(sprites.iter().map(|(_, overlay, ..)| overlay))
+        .filter(|overlay| overlay.layer == OverlayLayer::BelowText)
+        .map(|overlay| overlay.area)
+        .collect();
+    underlays.set_if_neq(TextUnderlays(below));
+    if viewport.rect.is_empty() {
+        return;
+    }
+
+    for (entity, overlay, mut node, z_index) in &mut nodes {
+        let rect = viewport.area_rect(overlay.area);
+        let (min, size) = (rect.min / ui_scale.0, rect.size() / ui_scale.0);
+        let placed = Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(min.x),
+            top: Val::Px(min.y),
+            width: Val::Px(size.x),
+            height: Val::Px(size.y),
+            ..node.clone()
+        };
+        node.set_if_neq(placed);
+        let z_index_wanted = overlay.layer.z_index();
+        if z_index != Some(&z_index_wanted) {
+            commands.entity(entity).insert(z_index_wanted);
+        }
+    }
+
+    let Some(camera) = camera else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    for (entity, overlay, mut sprite, mut transform, layers) in &mut sprites {
+        let rect = viewport.area_rect(overlay.area);
+        let (Ok(min), Ok(max)) = (
+            camera.viewport_to_world_2d(camera_transform, rect.min),
+            camera.viewport_to_world_2d(camera_transform, rect.max),
+        ) else {
+            continue;
+        };
+        let world = Rect::from_corners(min, max);
+        if sprite.custom_size != Some(world.size()) {
+            sprite.custom_size = Some(world.size());
+        }
+        let translation = world.center().extend(transform.translation.z);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+        let layers_wanted = overlay.layer.render_layers();
+        if layers != Some(&layers_wanted) {
+            commands.entity(entity).insert(layers_wanted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_background_under_overlays_is_transparent() {
+        // Two rows of two 1x1 cells, the last one under an overlay.
+        let underlays = TextUnderlays(vec![layout::Rect::new(1, 1, 1, 1)]);
+        let mut image = vec![0; 4 * 4];
+        image[8..11].copy_from_slice(&[255; 3]);
+        underlays.cut_out(&[0, 1], &mut image, 2, UVec2::ONE, [0; 3]);
+
+        let alpha: Vec<u8> = image.chunks(4).map(|rgba| rgba[3]).collect();
+        assert_eq!(alpha, [255, 255, 255, 0]);
+
+        // Once the overlay is gone, redrawn rows are opaque again.
+        TextUnderlays::default().cut_out(&[1], &mut image, 2, UVec2::ONE, [0; 3]);
+        assert!(image.chunks(4).all(|rgba| rgba[3] == 255));
+    }
+}
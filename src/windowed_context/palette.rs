@@ -0,0 +1,252 @@
+//! The color scheme of the terminal in windowed mode.
+use std::{error::Error, fmt};
+
+use bevy::prelude::*;
+use ratatui::style::Color;
+
+use crate::{
+    RatatuiContext,
+    layer::{XTERM_COLORS, indexed_rgb},
+};
+
+use super::window_terminal::WindowTerminal;
+
+/// The colors that named, indexed and default colors are drawn with in windowed mode, like a
+/// terminal's color scheme.
+///
+/// The colors of each frame drawn with [`WindowedContext::draw`][super::context::WindowedContext::draw]
+/// are mapped through the palette before they are rasterized, so changes apply from the next frame
+/// drawn. Defaults to xterm's colors, with white text on black. Themes can be loaded with
+/// [`from_theme`][Self::from_theme].
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct TerminalPalette {
+    /// The color of text without a foreground color.
+    pub foreground: [u8; 3],
+    /// The color of cells without a background color.
+    pub background: [u8; 3],
+    /// The 256 indexed colors: the 16 ANSI colors from [`Color::Black`] to [`Color::White`], then
+    /// the 6×6×6 color cube and 24 shades of gray.
+    pub colors: [[u8; 3]; 256],
+}
+
+impl Default for TerminalPalette {
+    fn default() -> Self {
+        Self {
+            foreground: [255, 255, 255],
+            background: [0, 0, 0],
+            colors: XTERM_COLORS,
+        }
+    }
+}
+
+impl TerminalPalette {
+    /// Loads a color scheme, keeping the default for colors it doesn't set.
+    ///
+    /// Reads the formats of kitty (`color0 #000000`), X resources (`*.color0: #000000`) and
+    /// Alacritty (`black = "#000000"` in `[colors.normal]`), with colors written as `#rrggbb`,
+    /// `#rgb`, `0xrrggbb` or `rgb:rr/gg/bb`.
+    pub fn from_theme(theme: &str) -> Result<Self, PaletteError> {
+        const NAMES: [&str; 8] = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+        let mut palette = Self::default();
+        let mut section = "";
+        let mut found = false;
+        for (number, line) in theme.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', '!', ';']) {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = name.trim();
+                continue;
+            }
+            let (key, value) = line.split_at(
+                line.find(|c: char| c.is_whitespace() || c == ':' || c == '=')
+                    .unwrap_or(line.len()),
+            );
+            // X resources are prefixed by the program and class, e.g. `URxvt*color1`.
+            let key = key.rsplit(['.', '*']).next().unwrap_or(key);
+            let value =
+                value.trim_start_matches(|c: char| c.is_whitespace() || c == ':' || c == '=');
+            let value = value.split_whitespace().next().unwrap_or_default();
+            let value = value.trim_matches(['"', '\'']);
+
+            let slot = match (section, key) {
+                ("colors.normal", name) => NAMES
+                    .iter()
+                    .position(|&n| n == name)
+                    .map(|i| &mut palette.colors[i]),
+                ("colors.bright", name) => NAMES
+                    .iter()
+                    .position(|&n| n == name)
+                    .map(|i| &mut palette.colors[i + 8]),
+                (_, "foreground") => Some(&mut palette.foreground),
+                (_, "background") => Some(&mut palette.background),
+                (_, key) => key
+                    .strip_prefix("color")
+                    .and_then(|index| index.parse::<u8>().ok())
+                    .map(|index| &mut palette.colors[index as usize]),
+            };
+            let Some(slot) = slot else {
+                continue;
+            };
+            *slot = parse_color(value).ok_or(PaletteError::InvalidColor { line: number + 1 })?;
+            found = true;
+        }
+        if !found {
+            return Err(PaletteError::NoColors);
+        }
+        Ok(palette)
+    }
+
+    /// The RGB value of a foreground color, using [`foreground`][Self::foreground] for
+    /// [`Color::Reset`].
+    pub fn foreground(&self, color: Color) -> [u8; 3] {
+        self.rgb(color).unwrap_or(self.foreground)
+    }
+
+    /// The RGB value of a background color, using [`background`][Self::background] for
+    /// [`Color::Reset`].
+    pub fn background(&self, color: Color) -> [u8; 3] {
+        self.rgb(color).unwrap_or(self.background)
+    }
+
+    /// The RGB value of a color, or `None` for [`Color::Reset`].
+    fn rgb(&self, color: Color) -> Option<[u8; 3]> {
+        indexed_rgb(color, &self.colors)
+    }
+}
+
+/// Parses a color written as `#rrggbb`, `#rgb`, `0xrrggbb` or `rgb:r/g/b` with one to four hex
+/// digits per component.
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let hex = |digits: &str| {
+        let valid =
+            (1..=4).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_hexdigit());
+        let value = u32::from_str_radix(digits, 16).ok().filter(|_| valid)?;
+        // Scale to 8 bits, e.g. `f` and `ffff` are both 255.
+        let max = (1 << (4 * digits.len())) - 1;
+        Some((value * 255 / max) as u8)
+    };
+    if let Some(digits) = value.strip_prefix('#').or_else(|| value.strip_prefix("0x")) {
+        let width = match digits.len() {
+            6 => 2,
+            3 => 1,
+            _ => return None,
+        };
+        let mut rgb = [0; 3];
+        for (channel, value) in rgb.iter_mut().enumerate() {
+            *value = hex(digits.get(channel * width..(channel + 1) * width)?)?;
+        }
+        return Some(rgb);
+    }
+    let mut channels = value.strip_prefix("rgb:")?.split('/');
+    let rgb = [
+        hex(channels.next()?)?,
+        hex(channels.next()?)?,
+        hex(channels.next()?)?,
+    ];
+    channels.next().is_none().then_some(rgb)
+}
+
+/// An error loading a [`TerminalPalette`] from a theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteError {
+    /// The color on this line couldn't be read.
+    InvalidColor {
+        /// The line number, starting at 1.
+        line: usize,
+    },
+    /// The theme sets none of the palette's colors.
+    NoColors,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::InvalidColor { line } => write!(f, "invalid color on line {line}"),
+            PaletteError::NoColors => write!(f, "the theme sets no colors"),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+/// System that hands a changed [`TerminalPalette`] to the terminal and the [`WindowTerminal`]s.
+pub(super) fn apply_terminal_palette(
+    palette: Res<TerminalPalette>,
+    mut context: ResMut<RatatuiContext>,
+    mut terminals: Query<&mut WindowTerminal>,
+) {
+    context.set_palette(palette.clone());
+    for mut terminal in &mut terminals {
+        terminal.set_palette(palette.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_color_cube_and_grays_follow_xterm() {
+        let palette = TerminalPalette::default();
+        assert_eq!(palette.rgb(Color::Indexed(16)), Some([0, 0, 0]));
+        assert_eq!(palette.rgb(Color::Indexed(196)), Some([255, 0, 0]));
+        assert_eq!(palette.rgb(Color::Indexed(231)), Some([255, 255, 255]));
+        assert_eq!(palette.rgb(Color::Indexed(232)), Some([8, 8, 8]));
+        assert_eq!(palette.rgb(Color::Indexed(255)), Some([238, 238, 238]));
+        assert_eq!(palette.rgb(Color::Red), Some([205, 0, 0]));
+        assert_eq!(palette.background(Color::Reset), [0, 0, 0]);
+    }
+
+    #[test]
+    fn colors_are_read_in_every_notation() {
+        assert_eq!(parse_color("#fdf6e3"), Some([253, 246, 227]));
+        assert_eq!(parse_color("0xFDF6E3"), Some([253, 246, 227]));
+        assert_eq!(parse_color("#f0a"), Some([255, 0, 170]));
+        assert_eq!(parse_color("rgb:ff/80/0"), Some([255, 128, 0]));
+        assert_eq!(parse_color("rgb:ffff/0000/8080"), Some([255, 0, 128]));
+        assert_eq!(parse_color("#ff00"), None);
+        assert_eq!(parse_color("#+f+f+f"), None);
+        assert_eq!(parse_color("rgb:ff/ff"), None);
+    }
+
+    #[test]
+    fn kitty_and_x_resources_themes_are_read() {
+        let kitty = "# Solarized\nforeground #839496\nbackground #002b36\ncolor1 #dc322f\n";
+        let palette = TerminalPalette::from_theme(kitty).unwrap();
+        assert_eq!(palette.foreground, [0x83, 0x94, 0x96]);
+        assert_eq!(palette.background, [0x00, 0x2b, 0x36]);
+        assert_eq!(palette.colors[1], [0xdc, 0x32, 0x2f]);
+        assert_eq!(palette.colors[2], TerminalPalette::default().colors[2]);
+
+        let xresources = "! Gruvbox\n*.background: #282828\nURxvt*color12:  rgb:83/a5/98\n";
+        let palette = TerminalPalette::from_theme(xresources).unwrap();
+        assert_eq!(palette.background, [0x28, 0x28, 0x28]);
+        assert_eq!(palette.colors[12], [0x83, 0xa5, 0x98]);
+    }
+
+    #[test]
+    fn alacritty_themes_are_read() {
+        let alacritty = "[colors.primary]\nbackground = '#282828'\ndim_foreground = '#000000'\n\
+            [colors.normal]\nred = \"0xcc241d\"\n[colors.bright]\nred = '#fb4934' # bold\n";
+        let palette = TerminalPalette::from_theme(alacritty).unwrap();
+        assert_eq!(palette.background, [0x28, 0x28, 0x28]);
+        assert_eq!(palette.foreground, TerminalPalette::default().foreground);
+        assert_eq!(palette.colors[1], [0xcc, 0x24, 0x1d]);
+        assert_eq!(palette.colors[9], [0xfb, 0x49, 0x34]);
+    }
+
+    #[test]
+    fn invalid_themes_are_errors() {
+        let error = TerminalPalette::from_theme("color0 #000000\ncolor1 red\n");
+        assert_eq!(error, Err(PaletteError::InvalidColor { line: 2 }));
+        let error = TerminalPalette::from_theme("font_size 12\n");
+        assert_eq!(error, Err(PaletteError::NoColors));
+    }
+}
@@ -1,109 +1,309 @@
 use bevy::{
     asset::RenderAssetUsages,
+    camera::visibility::RenderLayers,
+    ecs::system::SystemParam,
     prelude::*,
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
-    window::WindowResized,
+    ui::UiSystems,
+    window::{PrimaryWindow, WindowResized},
 };
+use ratatui::layout::Position;
 
-use crate::RatatuiContext;
+use crate::{
+    RatatuiContext,
+    input::{InputSet, ResizeMessage, update_terminal_size},
+    render::RenderSet,
+    shared_frame::flush_shared_frame,
+};
+
+use super::{
+    attributes::{TextBlink, draw_attributes},
+    context::WindowedContext,
+    cursor::{CursorBlink, WindowedCursor},
+    font::WindowedFont,
+    overlay::{CellOverlay, TextUnderlays, place_cell_overlays},
+    palette::{TerminalPalette, apply_terminal_palette},
+    scaling::{TerminalCamera, WindowedScaling, scalable_font, update_terminal_scaling},
+    texture::TerminalTexture,
+    viewport::{TerminalImageNode, TerminalPointer, TerminalViewport, update_terminal_viewport},
+    wide_glyphs::WideGlyphs,
+    window_terminal::{
+        WindowTerminalMessage, despawn_window_terminal_display, fit_window_terminals,
+        render_window_terminals, resize_window_terminals, spawn_window_terminal_displays,
+        update_window_terminal_viewports,
+    },
+};
 
 /// A plugin that, rather than drawing to a terminal buffer, uses software rendering to build a 2D
 /// texture from the ratatui buffer, and displays the result in a window.
-pub struct WindowedPlugin;
+///
+/// The texture is kept in [`TerminalImage`]. Unless [`spawn_display`][Self::spawn_display] is
+/// turned off, a camera and a UI node showing it in the middle of the window are spawned at
+/// startup, along with a camera drawing sprites above it. Sprites and images can be placed over
+/// cells with a [`CellOverlay`]. Other windows get terminals of their own with a
+/// [`WindowTerminal`][super::window_terminal::WindowTerminal].
+pub struct WindowedPlugin {
+    /// Whether to spawn the cameras and a UI node displaying the terminal over the whole window.
+    ///
+    /// Turn this off to show [`TerminalImage`] some other way, e.g. in a panel of the app's own UI
+    /// or as the texture of a mesh. Mouse input is mapped to cells through a node marked with
+    /// [`TerminalImageNode`], or through [`TerminalPointer`] for other placements. The grid still
+    /// fits the window according to [`WindowedScaling`], unless its mode is a
+    /// [`FixedGrid`][super::scaling::ScalingMode::FixedGrid].
+    pub spawn_display: bool,
+}
+
+impl Default for WindowedPlugin {
+    fn default() -> Self {
+        Self {
+            spawn_display: true,
+        }
+    }
+}
 
 impl Plugin for WindowedPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, terminal_render_setup)
-            .add_systems(PreUpdate, handle_resize_messages)
-            .add_systems(Update, render_terminal_to_handle);
+        app.init_resource::<TerminalViewport>()
+            .init_resource::<TerminalPointer>()
+            .init_resource::<WindowedScaling>()
+            .init_resource::<WideGlyphs>()
+            .init_resource::<WindowedCursor>()
+            .init_resource::<TerminalPalette>()
+            .init_resource::<TextUnderlays>()
+            .add_message::<WindowTerminalMessage>()
+            .add_observer(despawn_window_terminal_display)
+            .add_systems(
+                PreUpdate,
+                apply_terminal_palette.run_if(resource_changed::<TerminalPalette>),
+            )
+            .add_systems(
+                PreUpdate,
+                (update_terminal_viewport, update_window_terminal_viewports).in_set(InputSet::Pre),
+            )
+            .add_systems(
+                PreUpdate,
+                (handle_resize_messages, resize_window_terminals)
+                    .before(update_terminal_size)
+                    .in_set(InputSet::EmitCrossterm),
+            )
+            .add_systems(Update, spawn_window_terminal_displays)
+            .add_systems(
+                PostUpdate,
+                (
+                    // Once the frame shared by the systems drawing in the `RenderSet`s is drawn.
+                    (render_terminal_to_handle, render_window_terminals)
+                        .in_set(RenderSet::Flush)
+                        .after(flush_shared_frame::<RatatuiContext>),
+                    (update_terminal_scaling, fit_window_terminals).before(UiSystems::Layout),
+                    place_cell_overlays
+                        .before(UiSystems::Layout)
+                        .before(TransformSystems::Propagate),
+                ),
+            );
+        if self.spawn_display {
+            app.add_systems(PostStartup, terminal_render_setup);
+        }
     }
+
+    fn finish(&self, app: &mut App) {
+        // Created once the image assets exist, so the handle is available to startup systems.
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<Image>>()
+            .add(terminal_image());
+        app.insert_resource(TerminalImage(handle));
+    }
+}
+
+/// An image for a terminal to be drawn to, sized to the terminal on the first frame.
+pub(super) fn terminal_image() -> Image {
+    Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
 }
 
-#[derive(Resource)]
-struct TerminalRender(Handle<Image>);
+/// The image the terminal is drawn to in windowed mode, updated every frame something was drawn.
+#[derive(Resource, Clone, Debug, Deref)]
+pub struct TerminalImage(pub Handle<Image>);
 
-/// A startup system that sets up the terminal
+/// A startup system that spawns the cameras and the UI nodes displaying the terminal.
 pub fn terminal_render_setup(
     mut commands: Commands,
-    softatui: ResMut<RatatuiContext>,
-    mut images: ResMut<Assets<Image>>,
-) -> Result {
-    commands.spawn(Camera2d);
-    // Create an image that we are going to draw into
-    let width = softatui.backend().get_pixmap_width() as u32;
-    let height = softatui.backend().get_pixmap_height() as u32;
-    let data = softatui.backend().get_pixmap_data_as_rgba();
-
-    let image = Image::new(
-        Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
+    scaling: Res<WindowedScaling>,
+    image: Res<TerminalImage>,
+) {
+    commands.spawn((
+        Camera2d,
+        Camera {
+            clear_color: ClearColorConfig::Custom(scaling.background),
+            ..default()
         },
-        TextureDimension::D2,
-        data,
-        TextureFormat::Rgba8UnormSrgb,
-        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
-    );
-    let handle = images.add(image);
+        TerminalCamera,
+        IsDefaultUiCamera,
+    ));
+    // Draws the sprites above the text after the UI.
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        RenderLayers::layer(CellOverlay::RENDER_LAYER),
+    ));
     commands.spawn((
-        ImageNode::new(handle.clone()),
         Node {
-            justify_self: JustifySelf::Center,
-            align_self: AlignSelf::Center,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
             ..default()
         },
+        // Sized by `update_terminal_scaling`.
+        children![(
+            TerminalImageNode,
+            ImageNode::new(image.0.clone()).with_mode(NodeImageMode::Stretch),
+            Node::default(),
+        )],
     ));
-
-    commands.insert_resource(TerminalRender(handle));
-
-    Ok(())
 }
 
-/// System that updates the terminal texture each frame
+/// System that copies the rows of the terminal that changed to its texture.
 fn render_terminal_to_handle(
-    softatui: ResMut<RatatuiContext>,
-    mut images: ResMut<Assets<Image>>,
-    my_handle: Res<TerminalRender>,
+    softatui: Res<RatatuiContext>,
+    image: Res<TerminalImage>,
+    underlays: Res<TextUnderlays>,
+    mut resources: RenderResources,
+    mut renderer: Local<TerminalRenderer>,
 ) {
-    let width = softatui.backend().get_pixmap_width() as u32;
-    let height = softatui.backend().get_pixmap_height() as u32;
+    // Wide characters are drawn with other fonts, and overlays change which pixels are cut out.
+    let invalidate = resources.wide_glyphs.is_changed() || underlays.is_changed();
+    renderer.render(&softatui, &image.0, &underlays, invalidate, &mut resources);
+}
 
-    let mut image = images.get_mut(&my_handle.0).expect("Image not found");
-    if image.width() != width || image.height() != height {
-        image.resize(Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        });
-        image.data = Some(softatui.backend().get_pixmap_data_as_rgba());
-    } else {
-        // efficient fast-path copy using chunks
-        let data_in = softatui.backend().get_pixmap_data();
+/// What terminals are rendered with.
+#[derive(SystemParam)]
+pub(super) struct RenderResources<'w> {
+    images: ResMut<'w, Assets<Image>>,
+    pub(super) wide_glyphs: ResMut<'w, WideGlyphs>,
+    cursor: Res<'w, WindowedCursor>,
+    palette: Res<'w, TerminalPalette>,
+    time: Res<'w, Time>,
+}
+
+/// Renders a terminal to its image, remembering what the image shows.
+#[derive(Default, Debug)]
+pub(super) struct TerminalRenderer {
+    texture: TerminalTexture,
+    blink: CursorBlink,
+    text_blink: TextBlink,
+}
+
+impl TerminalRenderer {
+    /// Copies the rows of the terminal of `context` that changed to `image`, along with the text
+    /// attributes, the cursor and the cut-outs for overlays below the text, leaving the image
+    /// untouched when nothing was drawn and nothing blinked. `invalidate` copies every row.
+    pub(super) fn render(
+        &mut self,
+        context: &WindowedContext,
+        image: &Handle<Image>,
+        underlays: &TextUnderlays,
+        invalidate: bool,
+        resources: &mut RenderResources,
+    ) {
+        let backend = context.backend();
+        if invalidate {
+            self.texture.invalidate();
+        }
+        let Some(current) = resources.images.get(image) else {
+            return;
+        };
+        let mut rows = self.texture.dirty_rows(backend, current);
+        let elapsed = resources.time.elapsed();
+        let shown_text = TextBlink::at(elapsed);
+        if shown_text != self.text_blink {
+            rows.extend(context.blinking().rows());
+            self.text_blink = shown_text;
+        }
+        let cursor = &*resources.cursor;
+        let position = backend.cursor.then(|| Position::from(backend.cursor_pos));
+        let visible = self.blink.visible(cursor, position, elapsed);
+        if resources.cursor.is_changed() || visible != self.blink.drawn {
+            // The row the cursor leaves is copied again, and the one it enters drawn over.
+            rows.extend(self.blink.drawn.iter().chain(&visible).map(|cell| cell.y));
+        }
+        self.blink.drawn = visible;
+        let height = backend.buffer.area.height;
+        rows.retain(|&row| row < height);
+        rows.sort_unstable();
+        rows.dedup();
+        if rows.is_empty() {
+            return;
+        }
+
+        let palette = &*resources.palette;
+        let mut image = resources.images.get_mut(image).expect("Image not found");
+        self.texture.copy(backend, &mut image, &rows);
+        let width = image.width() as usize;
         let data_out = image.data.as_mut().expect("Image data missing");
-        let (pixels_in, _) = data_in.as_chunks::<3>();
-        let (pixels_out, _) = data_out.as_chunks_mut::<4>();
-        for i in 0..(width * height) as usize {
-            let px_out = &mut pixels_out[i];
-            let px_in = pixels_in[i];
-            px_out[0] = px_in[0];
-            px_out[1] = px_in[1];
-            px_out[2] = px_in[2];
-            // skip writing alpha as it is set to 255 by get_pixmap_data_as_rgba
+        resources.wide_glyphs.bypass_change_detection().draw(
+            &backend.buffer,
+            data_out,
+            width,
+            &rows,
+        );
+        let cell_size = UVec2::new(backend.char_width as u32, backend.char_height as u32);
+        draw_attributes(
+            &backend.buffer,
+            context.blinking(),
+            &rows,
+            data_out,
+            width,
+            cell_size,
+            context.decorations(),
+            shown_text,
+        );
+        if let Some(cell) = visible.filter(|cell| rows.contains(&cell.y)) {
+            cursor.draw(&backend.buffer, palette, cell, data_out, width, cell_size);
+        }
+        if !underlays.is_empty() || invalidate {
+            underlays.cut_out(&rows, data_out, width, cell_size, palette.background);
         }
     }
 }
 
-/// System that reacts to window resize
+/// System that reacts to window resize, fitting the terminal to the window according to the
+/// [`WindowedScaling`] and sending a [`ResizeMessage`] when the number of cells changes.
 fn handle_resize_messages(
     mut resize_reader: MessageReader<WindowResized>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    scaling: Res<WindowedScaling>,
+    font: Option<Res<WindowedFont>>,
     mut softatui: ResMut<RatatuiContext>,
+    mut resize: MessageWriter<ResizeMessage>,
 ) {
-    for message in resize_reader.read() {
-        let cur_pix_width = softatui.backend().char_width;
-        let cur_pix_height = softatui.backend().char_height;
-        let av_wid = (message.width / cur_pix_width as f32) as u16;
-        let av_hei = (message.height / cur_pix_height as f32) as u16;
-        softatui.backend_mut().resize(av_wid, av_hei);
+    // Only the final size of a burst of resizes matters.
+    if resize_reader.read().last().is_none() && !scaling.is_changed() {
+        return;
+    }
+    // Without a window, e.g. when running headless, there is nothing to fit the terminal to.
+    let Some(window) = window else {
+        return;
+    };
+    let char_size = UVec2::new(
+        softatui.backend().char_width as u32,
+        softatui.backend().char_height as u32,
+    );
+    let cells = scaling
+        .mode
+        .grid_size(&window, scalable_font(font.as_deref()), char_size);
+    if softatui.size().is_ok_and(|size| size == cells) {
+        return;
     }
+    softatui.backend_mut().resize(cells.width, cells.height);
+    resize.write(ResizeMessage(cells));
 }
@@ -0,0 +1,493 @@
+//! Animated recordings of the terminal in windowed mode.
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use async_channel::{Receiver, Sender, TrySendError};
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, futures::check_ready},
+};
+
+use crate::render::RenderSet;
+
+use super::plugin::TerminalImage;
+
+/// Plugin that records [`TerminalImage`] to an animated GIF or APNG file, between a
+/// [`StartRecordingMessage`] and a [`StopRecordingMessage`].
+///
+/// Frames are captured at a fixed rate, and a frame identical to the previous one only makes the
+/// previous one last longer, so idle periods take almost no space. Needs the `recording` feature,
+/// and isn't part of the [`RatatuiPlugins`][crate::RatatuiPlugins].
+///
+/// Frames are encoded on a background task as they are captured, and skipped while a second of
+/// frames is waiting for it. GIF files are written as the frames are encoded. APNG files start
+/// with their number of frames, so they are written once the recording stops, their frames kept
+/// compressed until then; a recording whose frames take more than 256 MiB is stopped.
+pub struct RecordingPlugin {
+    /// How many frames are captured per second.
+    pub frame_rate: u32,
+}
+
+impl Default for RecordingPlugin {
+    fn default() -> Self {
+        Self { frame_rate: 30 }
+    }
+}
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<StartRecordingMessage>()
+            .add_message::<StopRecordingMessage>()
+            .insert_resource(Recorder {
+                frame_rate: self.frame_rate.max(1),
+                recording: None,
+                encoding: Vec::new(),
+            })
+            .add_systems(PostUpdate, record_terminal.after(RenderSet::Flush));
+    }
+}
+
+/// Starts recording the terminal to a file, stopping the recording in progress if any.
+#[derive(Message, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct StartRecordingMessage {
+    /// The file to write once the recording stops.
+    pub path: PathBuf,
+    /// The format of the file.
+    pub format: RecordingFormat,
+}
+
+/// Stops the recording in progress, writing its file.
+#[derive(Message, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StopRecordingMessage;
+
+/// See [`StartRecordingMessage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum RecordingFormat {
+    /// An animated GIF, with at most 256 colors per frame and delays in hundredths of a second.
+    #[default]
+    Gif,
+    /// An animated PNG, keeping every color and the exact frame rate.
+    Apng,
+}
+
+/// An error writing a recording.
+#[derive(Debug)]
+pub enum RecordingError {
+    /// No frame was captured.
+    NoFrames,
+    /// The GIF couldn't be encoded.
+    Gif(gif::EncodingError),
+    /// The APNG couldn't be encoded.
+    Apng(png::EncodingError),
+    /// The file couldn't be written.
+    Io(io::Error),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::NoFrames => write!(f, "no frame was recorded"),
+            RecordingError::Gif(error) => write!(f, "failed to encode the GIF: {error}"),
+            RecordingError::Apng(error) => write!(f, "failed to encode the APNG: {error}"),
+            RecordingError::Io(error) => write!(f, "failed to write the recording: {error}"),
+        }
+    }
+}
+
+impl Error for RecordingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RecordingError::NoFrames => None,
+            RecordingError::Gif(error) => Some(error),
+            RecordingError::Apng(error) => Some(error),
+            RecordingError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<gif::EncodingError> for RecordingError {
+    fn from(error: gif::EncodingError) -> Self {
+        RecordingError::Gif(error)
+    }
+}
+
+impl From<png::EncodingError> for RecordingError {
+    fn from(error: png::EncodingError) -> Self {
+        RecordingError::Apng(error)
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+/// The recording in progress, and the recordings being written.
+#[derive(Resource)]
+struct Recorder {
+    frame_rate: u32,
+    recording: Option<Recording>,
+    encoding: Vec<Task<(PathBuf, Result<(), RecordingError>)>>,
+}
+
+/// A recording in progress, whose frames are sent to the task encoding them.
+struct Recording {
+    started: Duration,
+    /// The first tick not captured yet.
+    next_tick: u64,
+    /// Closed when the recording stops, which has the task finish the file.
+    frames: Sender<CapturedFrame>,
+}
+
+/// The RGBA pixels of a frame, and the tick it was captured at.
+struct CapturedFrame {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    tick: u64,
+}
+
+/// Captured frames, cropped or padded to the size of the first one. A frame is handed on once the
+/// next different one is captured, and so it is known how long it is shown for.
+#[derive(Debug, Default)]
+struct Frames {
+    width: u32,
+    height: u32,
+    /// The last frame handed in that differs from the one before, and the tick it was captured at.
+    last: Option<(Vec<u8>, u64)>,
+    /// The first tick after the last frame handed in.
+    end: u64,
+}
+
+impl Frames {
+    /// Adds the frame shown from its tick, returning the previous one and how many ticks it is
+    /// shown for, unless the two are the same.
+    fn push(&mut self, frame: CapturedFrame) -> Option<(Vec<u8>, u64)> {
+        if self.last.is_none() {
+            (self.width, self.height) = (frame.width, frame.height);
+        }
+        let rgba = fit(
+            frame.rgba,
+            frame.width,
+            frame.height,
+            self.width,
+            self.height,
+        );
+        self.end = frame.tick + 1;
+        if self.last.as_ref().is_some_and(|(last, _)| *last == rgba) {
+            return None;
+        }
+        let (previous, start) = self.last.replace((rgba, frame.tick))?;
+        Some((previous, frame.tick.saturating_sub(start).max(1)))
+    }
+
+    /// The last frame, and how many ticks it is shown for until the recording stopped.
+    fn finish(self) -> Option<(Vec<u8>, u64)> {
+        let (rgba, start) = self.last?;
+        Some((rgba, self.end.saturating_sub(start).max(1)))
+    }
+}
+
+/// Crops or pads RGBA pixels of one size to another, e.g. after the terminal was resized.
+fn fit(data: Vec<u8>, width: u32, height: u32, to_width: u32, to_height: u32) -> Vec<u8> {
+    if (width, height) == (to_width, to_height) {
+        return data;
+    }
+    let mut rgba = [0, 0, 0, 255].repeat((to_width * to_height) as usize);
+    let row = width.min(to_width) as usize * 4;
+    for y in 0..height.min(to_height) as usize {
+        let from = y * width as usize * 4;
+        let to = y * to_width as usize * 4;
+        rgba[to..to + row].copy_from_slice(&data[from..from + row]);
+    }
+    rgba
+}
+
+/// The memory the compressed frames of an APNG recording can take before it is stopped.
+const MAX_APNG_SIZE: usize = 256 << 20;
+
+/// Encodes the frames of a recording as they are handed on.
+struct Encoder {
+    width: u32,
+    height: u32,
+    frame_rate: u32,
+    output: Output,
+}
+
+enum Output {
+    /// Written to the file frame by frame.
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        /// How many ticks of frames were written.
+        shown: u64,
+    },
+    /// APNG files start with their number of frames, so the frames are kept in memory as PNG
+    /// images until the recording stops.
+    Apng {
+        frames: Vec<(Vec<u8>, u64)>,
+        size: usize,
+    },
+}
+
+impl Encoder {
+    fn new(
+        path: &Path,
+        format: RecordingFormat,
+        (width, height): (u32, u32),
+        frame_rate: u32,
+    ) -> Result<Self, RecordingError> {
+        let output = match format {
+            RecordingFormat::Gif => {
+                let file = create(path)?;
+                let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                Output::Gif { encoder, shown: 0 }
+            }
+            RecordingFormat::Apng => Output::Apng {
+                frames: Vec::new(),
+                size: 0,
+            },
+        };
+        Ok(Self {
+            width,
+            height,
+            frame_rate,
+            output,
+        })
+    }
+
+    /// Adds a frame shown for `ticks`, returning whether there is room for more.
+    fn write(&mut self, mut rgba: Vec<u8>, ticks: u64) -> Result<bool, RecordingError> {
+        match &mut self.output {
+            Output::Gif { encoder, shown } => {
+                // Delays are rounded from the start of the recording, so they don't drift.
+                let centiseconds = |tick: u64| tick * 100 / u64::from(self.frame_rate);
+                let (width, height) = (self.width as u16, self.height as u16);
+                let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+                frame.delay =
+                    (centiseconds(*shown + ticks) - centiseconds(*shown)).clamp(1, 0xffff) as u16;
+                *shown += ticks;
+                encoder.write_frame(&frame)?;
+                Ok(true)
+            }
+            Output::Apng { frames, size } => {
+                let mut png = Vec::new();
+                let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header()?;
+                writer.write_image_data(&rgba)?;
+                writer.finish()?;
+                *size += png.len();
+                frames.push((png, ticks));
+                Ok(*size < MAX_APNG_SIZE)
+            }
+        }
+    }
+
+    /// Finishes the file at `path`.
+    fn finish(self, path: &Path) -> Result<(), RecordingError> {
+        let frames = match self.output {
+            Output::Gif { encoder, .. } => {
+                // Writes the trailer.
+                encoder.into_inner()?.flush()?;
+                return Ok(());
+            }
+            Output::Apng { frames, .. } => frames,
+        };
+        let mut encoder = png::Encoder::new(create(path)?, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, 0)?;
+        let mut writer = encoder.write_header()?;
+        let frame_rate = self.frame_rate.min(u16::MAX.into()) as u16;
+        let mut rgba = Vec::new();
+        for (png, ticks) in frames {
+            let mut reader = png::Decoder::new(io::Cursor::new(png))
+                .read_info()
+                .map_err(io::Error::from)?;
+            rgba.resize(reader.output_buffer_size().unwrap_or_default(), 0);
+            reader.next_frame(&mut rgba).map_err(io::Error::from)?;
+            writer.set_frame_delay(ticks.min(u16::MAX.into()) as u16, frame_rate)?;
+            writer.write_image_data(&rgba)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Creates a file for writing, and its directory if needed.
+fn create(path: &Path) -> io::Result<BufWriter<File>> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+/// Encodes the frames received from `captured` on a background task, until the recording stops.
+fn encode(
+    path: PathBuf,
+    format: RecordingFormat,
+    frame_rate: u32,
+    captured: Receiver<CapturedFrame>,
+) -> Task<(PathBuf, Result<(), RecordingError>)> {
+    AsyncComputeTaskPool::get().spawn(async move {
+        let result = record(&path, format, frame_rate, captured).await;
+        (path, result)
+    })
+}
+
+async fn record(
+    path: &Path,
+    format: RecordingFormat,
+    frame_rate: u32,
+    captured: Receiver<CapturedFrame>,
+) -> Result<(), RecordingError> {
+    let mut frames = Frames::default();
+    let mut encoder = None;
+    while let Ok(frame) = captured.recv().await {
+        let Some((rgba, ticks)) = frames.push(frame) else {
+            continue;
+        };
+        let size = (frames.width, frames.height);
+        let encoder = match &mut encoder {
+            Some(encoder) => encoder,
+            None => encoder.insert(Encoder::new(path, format, size, frame_rate)?),
+        };
+        if !encoder.write(rgba, ticks)? {
+            tracing::warn!(
+                "Stopped recording to {}: the frames take too much memory",
+                path.display()
+            );
+            break;
+        }
+    }
+    // Stops the capture if the recording was stopped here.
+    drop(captured);
+    let size = (frames.width, frames.height);
+    let (rgba, ticks) = frames.finish().ok_or(RecordingError::NoFrames)?;
+    let mut encoder = match encoder {
+        Some(encoder) => encoder,
+        None => Encoder::new(path, format, size, frame_rate)?,
+    };
+    encoder.write(rgba, ticks)?;
+    encoder.finish(path)
+}
+
+fn record_terminal(
+    mut starts: MessageReader<StartRecordingMessage>,
+    mut stops: MessageReader<StopRecordingMessage>,
+    mut recorder: ResMut<Recorder>,
+    images: Res<Assets<Image>>,
+    image: Res<TerminalImage>,
+    time: Res<Time>,
+) {
+    let recorder = &mut *recorder;
+    recorder.encoding.retain_mut(|task| {
+        let Some((path, result)) = check_ready(task) else {
+            return true;
+        };
+        if let Err(error) = result {
+            tracing::warn!("Failed to record to {}: {error}", path.display());
+        }
+        false
+    });
+
+    let start = starts.read().last().cloned();
+    if stops.read().count() > 0 || start.is_some() {
+        // Closes the channel, which has the recording's file finished.
+        recorder.recording = None;
+    }
+    if let Some(start) = start {
+        // Up to a second of frames waits for the encoder, after which frames are skipped.
+        let (frames, captured) = async_channel::bounded(recorder.frame_rate as usize);
+        recorder.encoding.push(encode(
+            start.path,
+            start.format,
+            recorder.frame_rate,
+            captured,
+        ));
+        recorder.recording = Some(Recording {
+            started: time.elapsed(),
+            next_tick: 0,
+            frames,
+        });
+    }
+
+    let Some(recording) = recorder.recording.as_mut() else {
+        return;
+    };
+    let since_start = time.elapsed() - recording.started;
+    let tick = (since_start.as_nanos() * u128::from(recorder.frame_rate) / 1_000_000_000) as u64;
+    if tick < recording.next_tick {
+        return;
+    }
+    let Some(image) = images.get(&image.0) else {
+        return;
+    };
+    let Some(rgba) = image.data.clone() else {
+        return;
+    };
+    let frame = CapturedFrame {
+        rgba,
+        width: image.width(),
+        height: image.height(),
+        tick,
+    };
+    match recording.frames.try_send(frame) {
+        Ok(()) => recording.next_tick = tick + 1,
+        // The encoder is behind: the previous frame is shown for longer.
+        Err(TrySendError::Full(_)) => {}
+        // The encoder stopped, and reports why once it is done.
+        Err(TrySendError::Closed(_)) => recorder.recording = None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, value: u8, tick: u64) -> CapturedFrame {
+        CapturedFrame {
+            rgba: [value, value, value, 255].repeat(width as usize),
+            width,
+            height: 1,
+            tick,
+        }
+    }
+
+    #[test]
+    fn identical_frames_are_merged() {
+        let mut frames = Frames::default();
+        let mut durations: Vec<u64> = [frame(2, 0, 0), frame(2, 0, 1), frame(2, 255, 4)]
+            .into_iter()
+            .filter_map(|frame| frames.push(frame))
+            .map(|(_, ticks)| ticks)
+            .collect();
+        assert!(frames.push(frame(2, 255, 5)).is_none());
+        durations.extend(frames.finish().map(|(_, ticks)| ticks));
+        assert_eq!(durations, [4, 2]);
+    }
+
+    #[test]
+    fn resized_frames_are_cropped_or_padded() {
+        let mut frames = Frames::default();
+        frames.push(frame(2, 10, 0));
+        let cropped = frames.push(frame(3, 20, 1));
+        let padded = frames.push(frame(1, 30, 2));
+
+        assert_eq!(cropped.unwrap().0, [10, 10, 10, 255].repeat(2));
+        assert_eq!(padded.unwrap().0, [20, 20, 20, 255].repeat(2));
+        assert_eq!(
+            frames.finish().unwrap().0,
+            [[30, 30, 30, 255], [0, 0, 0, 255]].concat()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! How the terminal is fitted to the window in windowed mode.
+use bevy::{image::ImageSampler, prelude::*, window::PrimaryWindow};
+use ratatui::layout::Size;
+
+use crate::RatatuiContext;
+
+use super::{context::WindowedContext, font::WindowedFont, viewport::TerminalImageNode};
+
+/// How the terminal is fitted to the window, and the color shown around it.
+///
+/// Changing this resource refits the grid to the window, sending a
+/// [`ResizeMessage`][crate::event::ResizeMessage] if the number of cells changes.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct WindowedScaling {
+    /// How the grid is sized and the terminal magnified to the window.
+    pub mode: ScalingMode,
+    /// The color of the window around the terminal.
+    pub background: Color,
+}
+
+impl Default for WindowedScaling {
+    fn default() -> Self {
+        Self {
+            mode: ScalingMode::default(),
+            background: Color::BLACK,
+        }
+    }
+}
+
+/// See [`WindowedScaling`].
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ScalingMode {
+    /// Fill the window with as many cells as fit, adding cells as it grows.
+    ///
+    /// Outline fonts are rasterized at the window's scale factor, so their
+    /// [size][WindowedFont::size] is in logical pixels and text stays sharp on HiDPI displays. The
+    /// built-in bitmap font is magnified by the scale factor rounded to a whole number.
+    #[default]
+    Fill,
+    /// Fill the window with as many cells as fit, magnifying every pixel of the terminal into a
+    /// square of this many logical pixels without smoothing, for a pixelated look.
+    ///
+    /// Fonts are rasterized at their size, then magnified with the rest of the terminal.
+    Integer(u32),
+    /// Keep this many cells, and scale the terminal smoothly to the largest size that fits the
+    /// window.
+    FixedGrid(Size),
+}
+
+impl ScalingMode {
+    /// How much larger than their size in logical pixels fonts are rasterized. Without a window,
+    /// e.g. when running headless, they are rasterized at their size.
+    pub(crate) fn font_scale(&self, window: Option<&Window>) -> f32 {
+        match self {
+            ScalingMode::Integer(_) => 1.0,
+            ScalingMode::Fill | ScalingMode::FixedGrid(_) => {
+                window.map_or(1.0, Window::scale_factor)
+            }
+        }
+    }
+
+    /// How many physical pixels each pixel of the terminal covers.
+    ///
+    /// `scalable_font` tells whether the terminal is drawn with an outline font, which is already
+    /// rasterized at the window's scale factor when filling the window.
+    fn texture_scale(&self, window: &Window, scalable_font: bool, texture: Vec2) -> f32 {
+        let whole_scale_factor = window.scale_factor().round().max(1.0);
+        match *self {
+            ScalingMode::Fill if scalable_font => 1.0,
+            ScalingMode::Fill => whole_scale_factor,
+            ScalingMode::Integer(scale) => scale.max(1) as f32 * whole_scale_factor,
+            ScalingMode::FixedGrid(_) if texture.cmpgt(Vec2::ZERO).all() => {
+                (window.physical_size().as_vec2() / texture).min_element()
+            }
+            ScalingMode::FixedGrid(_) => 1.0,
+        }
+    }
+
+    /// The number of cells of `char_size` pixels that fit the window.
+    pub(crate) fn grid_size(&self, window: &Window, scalable_font: bool, char_size: UVec2) -> Size {
+        if let ScalingMode::FixedGrid(size) = *self {
+            return size;
+        }
+        let cell = char_size.max(UVec2::ONE).as_vec2()
+            * self.texture_scale(window, scalable_font, Vec2::ZERO);
+        let cells = (window.physical_size().as_vec2() / cell).max(Vec2::ONE);
+        Size::new(cells.x as u16, cells.y as u16)
+    }
+}
+
+/// Whether the terminal is meant to be drawn with an outline font.
+pub(crate) fn scalable_font(font: Option<&WindowedFont>) -> bool {
+    font.is_some_and(|font| font.regular.is_some())
+}
+
+/// Marks the camera whose clear color fills the window around the terminal.
+#[derive(Component)]
+pub(crate) struct TerminalCamera;
+
+/// System that sizes the terminal's node to the [`WindowedScaling`], picking the filtering that
+/// keeps whole-number magnifications sharp.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_terminal_scaling(
+    scaling: Res<WindowedScaling>,
+    font: Option<Res<WindowedFont>>,
+    context: Res<RatatuiContext>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut image_node: Single<(&ImageNode, &mut Node), With<TerminalImageNode>>,
+    mut camera: Single<&mut Camera, With<TerminalCamera>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (image_node, node) = &mut *image_node;
+    fit_terminal_node(
+        &scaling,
+        scalable_font(font.as_deref()),
+        &context,
+        &window,
+        image_node,
+        node,
+        &mut camera,
+        &mut images,
+    );
+}
+
+/// Sizes the node showing the terminal of `context` in `window` to the [`WindowedScaling`],
+/// clearing the window around it with the scaling's background.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn fit_terminal_node(
+    scaling: &WindowedScaling,
+    scalable_font: bool,
+    context: &WindowedContext,
+    window: &Window,
+    image_node: &ImageNode,
+    node: &mut Node,
+    camera: &mut Camera,
+    images: &mut Assets<Image>,
+) {
+    let backend = context.backend();
+    let texture = Vec2::new(
+        backend.get_pixmap_width() as f32,
+        backend.get_pixmap_height() as f32,
+    );
+    let scale = scaling.mode.texture_scale(window, scalable_font, texture);
+    let size = texture * scale / window.scale_factor();
+
+    if node.width != Val::Px(size.x) || node.height != Val::Px(size.y) {
+        node.width = Val::Px(size.x);
+        node.height = Val::Px(size.y);
+    }
+    // The window is cleared rather than covered by a node, so sprites below the text show.
+    if !matches!(camera.clear_color, ClearColorConfig::Custom(color) if color == scaling.background)
+    {
+        camera.clear_color = ClearColorConfig::Custom(scaling.background);
+    }
+
+    let sampler = if scale.fract() == 0.0 {
+        ImageSampler::nearest()
+    } else {
+        ImageSampler::linear()
+    };
+    if images
+        .get(&image_node.image)
+        .is_some_and(|image| image.sampler != sampler)
+        && let Some(mut image) = images.get_mut(&image_node.image)
+    {
+        image.sampler = sampler;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::window::WindowResolution;
+
+    use super::*;
+
+    fn window(width: u32, height: u32, scale_factor: f32) -> Window {
+        Window {
+            resolution: WindowResolution::new(width, height)
+                .with_scale_factor_override(scale_factor),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn filling_the_window_adds_cells() {
+        let window = window(800, 600, 1.0);
+        let grid = ScalingMode::Fill.grid_size(&window, true, UVec2::new(10, 20));
+        assert_eq!(grid, Size::new(80, 30));
+        let grid = ScalingMode::Integer(2).grid_size(&window, true, UVec2::new(10, 20));
+        assert_eq!(grid, Size::new(40, 15));
+    }
+
+    #[test]
+    fn bitmap_fonts_are_magnified_on_hidpi_displays() {
+        let window = window(1600, 1200, 2.0);
+        // Outline fonts are already rasterized at twice the size.
+        let grid = ScalingMode::Fill.grid_size(&window, true, UVec2::new(20, 40));
+        assert_eq!(grid, Size::new(80, 30));
+        let grid = ScalingMode::Fill.grid_size(&window, false, UVec2::new(10, 20));
+        assert_eq!(grid, Size::new(80, 30));
+        assert_eq!(ScalingMode::Integer(2).font_scale(Some(&window)), 1.0);
+        assert_eq!(
+            ScalingMode::Integer(2).texture_scale(&window, true, Vec2::ZERO),
+            4.0
+        );
+    }
+
+    #[test]
+    fn fixed_grids_scale_to_fit() {
+        let window = window(800, 600, 1.0);
+        let mode = ScalingMode::FixedGrid(Size::new(40, 10));
+        assert_eq!(
+            mode.grid_size(&window, true, UVec2::new(10, 20)),
+            Size::new(40, 10)
+        );
+        let scale = mode.texture_scale(&window, true, Vec2::new(400.0, 200.0));
+        assert_eq!(scale, 2.0);
+    }
+}
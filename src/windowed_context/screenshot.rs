@@ -0,0 +1,285 @@
+//! PNG screenshots of the terminal in windowed mode.
+//!
+//! Screenshots are taken from [`TerminalImage`], which is drawn on the CPU, so they work without
+//! a window or a GPU, e.g. in visual tests on CI.
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, futures::check_ready},
+};
+
+use crate::render::RenderSet;
+
+use super::plugin::TerminalImage;
+
+/// Plugin that saves [`TerminalImage`] as PNG files when asked with a [`ScreenshotMessage`] or a
+/// [`FrameDumpMessage`], or when the screenshot key is pressed.
+///
+/// Frames are taken at the end of the frame, once the terminal was drawn, so a screenshot asked
+/// for in [`Update`] shows the frame drawn in it, including what was drawn in the
+/// [`RenderSet`]s. Screenshots are written right away, and dumped frames on background tasks.
+pub struct WindowedScreenshotPlugin {
+    /// The key that saves a screenshot in [`directory`][Self::directory], or `None` for no key.
+    /// Only has an effect with the `keyboard` feature.
+    ///
+    /// The key presses are still sent as [`KeyMessage`][crate::event::KeyMessage]s.
+    pub hotkey: Option<KeyCode>,
+    /// Where the screenshot key saves screenshots, numbered from `screenshot-0000.png`.
+    pub directory: PathBuf,
+}
+
+impl Default for WindowedScreenshotPlugin {
+    fn default() -> Self {
+        Self {
+            hotkey: Some(KeyCode::F12),
+            directory: PathBuf::from("screenshots"),
+        }
+    }
+}
+
+impl Plugin for WindowedScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ScreenshotMessage>()
+            .add_message::<FrameDumpMessage>()
+            .add_systems(
+                PostUpdate,
+                (save_screenshots, dump_frames).after(RenderSet::Flush),
+            );
+        #[cfg(feature = "keyboard")]
+        if let Some(key) = self.hotkey {
+            app.insert_resource(ScreenshotKey {
+                key,
+                directory: self.directory.clone(),
+            })
+            .add_systems(Update, screenshot_pressed);
+        }
+    }
+}
+
+/// Saves the terminal as a PNG file at this path, creating its directory if needed.
+#[derive(Message, Deref, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ScreenshotMessage(pub PathBuf);
+
+/// Saves every frame for a while as numbered PNG files, from `frame-00000.png`, in a directory
+/// created if needed.
+///
+/// A new dump replaces the one in progress.
+#[derive(Message, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FrameDumpMessage {
+    /// Where the frames are saved.
+    pub directory: PathBuf,
+    /// How long frames are saved for, starting with the current frame.
+    pub duration: Duration,
+}
+
+/// Encodes an RGBA image, such as [`TerminalImage`], as a PNG file.
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, ScreenshotError> {
+    let data = image.data.as_deref().ok_or(ScreenshotError::NoImage)?;
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// Saves an RGBA image as a PNG file, creating its directory if needed.
+pub fn save_png(image: &Image, path: &Path) -> Result<(), ScreenshotError> {
+    let png = encode_png(image)?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(path, png)?;
+    Ok(())
+}
+
+/// An error saving a screenshot.
+#[derive(Debug)]
+pub enum ScreenshotError {
+    /// The image has no data on the CPU.
+    NoImage,
+    /// The image couldn't be encoded.
+    Encoding(png::EncodingError),
+    /// The file couldn't be written.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::NoImage => write!(f, "the image has no data"),
+            ScreenshotError::Encoding(error) => write!(f, "failed to encode the image: {error}"),
+            ScreenshotError::Io(error) => write!(f, "failed to write the image: {error}"),
+        }
+    }
+}
+
+impl Error for ScreenshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScreenshotError::NoImage => None,
+            ScreenshotError::Encoding(error) => Some(error),
+            ScreenshotError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<png::EncodingError> for ScreenshotError {
+    fn from(error: png::EncodingError) -> Self {
+        ScreenshotError::Encoding(error)
+    }
+}
+
+impl From<std::io::Error> for ScreenshotError {
+    fn from(error: std::io::Error) -> Self {
+        ScreenshotError::Io(error)
+    }
+}
+
+/// The key that saves screenshots, and where.
+#[cfg(feature = "keyboard")]
+#[derive(Resource)]
+struct ScreenshotKey {
+    key: KeyCode,
+    directory: PathBuf,
+}
+
+#[cfg(feature = "keyboard")]
+fn screenshot_pressed(
+    keys: Res<ButtonInput<KeyCode>>,
+    hotkey: Res<ScreenshotKey>,
+    mut screenshots: MessageWriter<ScreenshotMessage>,
+) {
+    if keys.just_pressed(hotkey.key) {
+        screenshots.write(ScreenshotMessage(unused_path(&hotkey.directory)));
+    }
+}
+
+/// The first `screenshot-NNNN.png` in `directory` that doesn't exist yet.
+#[cfg(feature = "keyboard")]
+fn unused_path(directory: &Path) -> PathBuf {
+    (0..)
+        .map(|index| directory.join(format!("screenshot-{index:04}.png")))
+        .find(|path| !path.exists())
+        .expect("ran out of screenshot names")
+}
+
+fn save_screenshots(
+    mut screenshots: MessageReader<ScreenshotMessage>,
+    images: Res<Assets<Image>>,
+    image: Res<TerminalImage>,
+) {
+    let Some(image) = images.get(&image.0) else {
+        return;
+    };
+    for ScreenshotMessage(path) in screenshots.read() {
+        if let Err(error) = save_png(image, path) {
+            tracing::warn!("Failed to save a screenshot to {}: {error}", path.display());
+        }
+    }
+}
+
+/// A frame dump in progress.
+struct FrameDump {
+    directory: PathBuf,
+    until: Duration,
+    frames: u32,
+}
+
+/// The frame dump in progress, and the frames being written.
+#[derive(Default)]
+struct FrameDumps {
+    dump: Option<FrameDump>,
+    saving: Vec<Task<(PathBuf, Result<(), ScreenshotError>)>>,
+}
+
+fn dump_frames(
+    mut dumps: MessageReader<FrameDumpMessage>,
+    images: Res<Assets<Image>>,
+    image: Res<TerminalImage>,
+    time: Res<Time>,
+    mut state: Local<FrameDumps>,
+) {
+    let FrameDumps { dump, saving } = &mut *state;
+    saving.retain_mut(|task| {
+        let Some((path, result)) = check_ready(task) else {
+            return true;
+        };
+        if let Err(error) = result {
+            tracing::warn!("Failed to save a frame to {}: {error}", path.display());
+            *dump = None;
+        }
+        false
+    });
+
+    if let Some(message) = dumps.read().last() {
+        *dump = Some(FrameDump {
+            directory: message.directory.clone(),
+            until: time.elapsed() + message.duration,
+            frames: 0,
+        });
+    }
+    let Some(frame_dump) = dump.as_mut() else {
+        return;
+    };
+    if time.elapsed() >= frame_dump.until {
+        *dump = None;
+        return;
+    }
+    let Some(image) = images.get(&image.0) else {
+        return;
+    };
+    let path = frame_dump
+        .directory
+        .join(format!("frame-{:05}.png", frame_dump.frames));
+    frame_dump.frames += 1;
+    let image = image.clone();
+    saving.push(AsyncComputeTaskPool::get().spawn(async move {
+        let result = save_png(&image, &path);
+        (path, result)
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::RenderAssetUsages,
+        render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    };
+
+    use super::*;
+
+    #[test]
+    fn screenshots_keep_every_pixel() {
+        let pixels = [[255, 0, 0, 255], [0, 0, 255, 0]].concat();
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            pixels.clone(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::MAIN_WORLD,
+        );
+        let png = encode_png(&image).unwrap();
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(decoded, pixels);
+    }
+}
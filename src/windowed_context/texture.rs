@@ -0,0 +1,131 @@
+//! Copying the soft backend's pixels into the terminal's image.
+use bevy::{prelude::*, render::render_resource::Extent3d};
+use ratatui::buffer::Buffer;
+use soft_ratatui::{EmbeddedGraphics, SoftBackend};
+
+/// Keeps an RGBA [`Image`] in sync with the pixels drawn by a [`SoftBackend`], converting only the
+/// rows of cells that changed since the last copy.
+///
+/// [`WindowedPlugin`][super::plugin::WindowedPlugin] uses this to update the terminal's image,
+/// and leaves the image untouched on frames where nothing was drawn, so it isn't uploaded again.
+#[derive(Default, Debug)]
+pub struct TerminalTexture {
+    /// The cells of the last copy, or `None` if everything has to be copied.
+    drawn: Option<Buffer>,
+}
+
+impl TerminalTexture {
+    /// The rows of cells that differ from the image. Empty when the image is up to date.
+    pub fn dirty_rows(&self, backend: &SoftBackend<EmbeddedGraphics>, image: &Image) -> Vec<u16> {
+        let buffer = &backend.buffer;
+        match &self.drawn {
+            Some(drawn)
+                if drawn.area == buffer.area
+                    && image.width() == backend.get_pixmap_width() as u32
+                    && image.height() == backend.get_pixmap_height() as u32 =>
+            {
+                changed_rows(drawn, buffer)
+            }
+            _ => (0..buffer.area.height).collect(),
+        }
+    }
+
+    /// Copies the pixels of the given rows of cells to the image, resizing it to the backend's
+    /// size if needed, in which case every row is copied.
+    pub fn copy(
+        &mut self,
+        backend: &SoftBackend<EmbeddedGraphics>,
+        image: &mut Image,
+        rows: &[u16],
+    ) {
+        let width = backend.get_pixmap_width() as u32;
+        let height = backend.get_pixmap_height() as u32;
+        if image.width() != width || image.height() != height {
+            image.resize(Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            });
+            image.data = Some(backend.get_pixmap_data_as_rgba());
+        } else {
+            let data_out = image.data.as_mut().expect("Image data missing");
+            copy_rows(
+                backend.get_pixmap_data(),
+                data_out,
+                width as usize * backend.char_height,
+                rows,
+            );
+        }
+        match &mut self.drawn {
+            Some(drawn) => drawn.clone_from(&backend.buffer),
+            None => self.drawn = Some(backend.buffer.clone()),
+        }
+    }
+
+    /// Makes the next copy include every row, e.g. after something else was drawn on the image.
+    pub fn invalidate(&mut self) {
+        self.drawn = None;
+    }
+}
+
+/// The rows whose cells differ between two buffers of the same area.
+fn changed_rows(previous: &Buffer, current: &Buffer) -> Vec<u16> {
+    let width = current.area.width as usize;
+    if width == 0 {
+        return Vec::new();
+    }
+    previous
+        .content
+        .chunks(width)
+        .zip(current.content.chunks(width))
+        .enumerate()
+        .filter(|(_, (previous, current))| previous != current)
+        .map(|(row, _)| row as u16)
+        .collect()
+}
+
+/// Converts rows of RGB pixels to RGBA, `row_pixels` pixels per row of cells.
+fn copy_rows(rgb: &[u8], rgba: &mut [u8], row_pixels: usize, rows: &[u16]) {
+    let pixels = (rgb.len() / 3).min(rgba.len() / 4);
+    for &row in rows {
+        let start = (row as usize * row_pixels).min(pixels);
+        let end = (start + row_pixels).min(pixels);
+        let (pixels_in, _) = rgb[start * 3..end * 3].as_chunks::<3>();
+        let (pixels_out, _) = rgba[start * 4..end * 4].as_chunks_mut::<4>();
+        for (px_out, px_in) in pixels_out.iter_mut().zip(pixels_in) {
+            // skip writing alpha as it is set to 255 by get_pixmap_data_as_rgba
+            px_out[..3].copy_from_slice(px_in);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::*;
+
+    #[test]
+    fn only_rows_with_changed_cells_are_dirty() {
+        let previous = Buffer::with_lines(["abc", "def", "ghi"]);
+        let mut current = previous.clone();
+        assert!(changed_rows(&previous, &current).is_empty());
+
+        current[(1, 1)].set_symbol("x");
+        current[(0, 2)].set_fg(ratatui::style::Color::Red);
+        assert_eq!(changed_rows(&previous, &current), [1, 2]);
+        assert!(changed_rows(&Buffer::empty(Rect::ZERO), &Buffer::empty(Rect::ZERO)).is_empty());
+    }
+
+    #[test]
+    fn only_dirty_rows_are_copied() {
+        let rgb: Vec<u8> = (0..3 * 4 * 3).map(|i| i as u8).collect();
+        let mut rgba = vec![255; 4 * 4 * 3];
+        copy_rows(&rgb, &mut rgba, 4, &[1]);
+
+        assert!(rgba[..16].iter().all(|&value| value == 255));
+        assert_eq!(&rgba[16..20], &[12, 13, 14, 255]);
+        assert_eq!(&rgba[28..32], &[21, 22, 23, 255]);
+        assert!(rgba[32..].iter().all(|&value| value == 255));
+    }
+}
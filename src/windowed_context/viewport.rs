@@ -0,0 +1,210 @@
+//! Mapping between window positions and terminal cells in windowed mode.
+use bevy::{
+    prelude::*,
+    ui::{ComputedNode, UiGlobalTransform},
+    window::PrimaryWindow,
+};
+use ratatui::layout::{self, Position, Size};
+
+use crate::RatatuiContext;
+
+use super::context::WindowedContext;
+
+/// Marks the UI node that displays the terminal's image, which [`TerminalViewport`] follows.
+///
+/// Spawned by [`WindowedPlugin`][super::plugin::WindowedPlugin] unless its display is turned off,
+/// in which case it can be added to a node of the app's own UI showing
+/// [`TerminalImage`][super::plugin::TerminalImage]. There should be at most one.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct TerminalImageNode;
+
+/// Where the terminal is displayed in the window, in logical pixels.
+///
+/// Updated every frame from the [`TerminalImageNode`] displaying the terminal, accounting for its
+/// position, its scale, and the letterboxing added when the node's aspect ratio differs from the
+/// terminal's. Without such a node, the area is empty and only the number of cells is kept.
+/// Positions reported by bevy, such as [`Window::cursor_position`], can be mapped to cells with
+/// [`cell_at`][Self::cell_at], and cells back to window positions with
+/// [`cell_rect`][Self::cell_rect] or [`area_rect`][Self::area_rect].
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct TerminalViewport {
+    /// The area of the window the terminal is drawn in.
+    pub rect: Rect,
+    /// The size of the terminal in cells.
+    pub cells: Size,
+}
+
+impl TerminalViewport {
+    /// The size of a single cell.
+    pub fn cell_size(&self) -> Vec2 {
+        self.rect.size() / Vec2::new(self.cells.width.into(), self.cells.height.into())
+    }
+
+    /// Returns the cell under a position in the window, or `None` if the position is outside the
+    /// terminal.
+    pub fn cell_at(&self, position: Vec2) -> Option<Position> {
+        if self.cells.width == 0 || self.cells.height == 0 {
+            return None;
+        }
+        let cell = ((position - self.rect.min) / self.cell_size()).floor();
+        let in_bounds = cell.cmpge(Vec2::ZERO).all()
+            && cell.x < f32::from(self.cells.width)
+            && cell.y < f32::from(self.cells.height);
+        in_bounds.then(|| Position::new(cell.x as u16, cell.y as u16))
+    }
+
+    /// Returns the cell at a position on the terminal's image, in UV coordinates from `(0, 0)` at
+    /// its top-left corner to `(1, 1)` at its bottom-right, or `None` if the position is outside.
+    pub fn cell_at_uv(&self, uv: Vec2) -> Option<Position> {
+        let cells = Vec2::new(self.cells.width.into(), self.cells.height.into());
+        let cell = (uv * cells).floor();
+        let in_bounds = cell.cmpge(Vec2::ZERO).all() && cell.cmplt(cells).all();
+        in_bounds.then(|| Position::new(cell.x as u16, cell.y as u16))
+    }
+
+    /// Returns the area of the window covered by a cell.
+    pub fn cell_rect(&self, cell: Position) -> Rect {
+        let size = self.cell_size();
+        let min = self.rect.min + Vec2::new(cell.x.into(), cell.y.into()) * size;
+        Rect::from_corners(min, min + size)
+    }
+
+    /// Returns the area of the window covered by an area of cells, such as a ratatui layout region.
+    pub fn area_rect(&self, area: layout::Rect) -> Rect {
+        let min = self.cell_rect(area.as_position()).min;
+        Rect::from_corners(
+            min,
+            min + Vec2::new(area.width.into(), area.height.into()) * self.cell_size(),
+        )
+    }
+}
+
+/// Where mouse input is reported on the terminal in windowed mode.
+///
+/// By default, the window's cursor is mapped to cells through the [`TerminalViewport`]. When the
+/// terminal's image is displayed somewhere the viewport can't describe, such as on a mesh in a 3D
+/// scene, the app can instead set the position of the pointer on the image itself, e.g. from the
+/// UV coordinates of a picking hit.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub enum TerminalPointer {
+    /// Map the window's cursor through the [`TerminalViewport`].
+    #[default]
+    Window,
+    /// The pointer is at this position on the terminal's image, in UV coordinates (see
+    /// [`TerminalViewport::cell_at_uv`]), or isn't over it if `None`.
+    Uv(Option<Vec2>),
+}
+
+/// Returns the largest area with the aspect ratio of `content` that fits centered in `area`.
+fn letterbox(area: Rect, content: Vec2) -> Rect {
+    if content.x <= 0.0 || content.y <= 0.0 {
+        return area;
+    }
+    let scale = (area.size() / content).min_element();
+    Rect::from_center_size(area.center(), content * scale)
+}
+
+/// System that keeps [`TerminalViewport`] up to date with the terminal's node.
+pub(crate) fn update_terminal_viewport(
+    node: Option<Single<(&ComputedNode, &UiGlobalTransform), With<TerminalImageNode>>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    context: Res<RatatuiContext>,
+    mut viewport: ResMut<TerminalViewport>,
+) -> Result {
+    let cells = context.size()?;
+    let Some(node) = node else {
+        // The terminal isn't displayed in the window's UI, so it covers no area of the window.
+        viewport.set_if_neq(TerminalViewport {
+            rect: Rect::default(),
+            cells,
+        });
+        return Ok(());
+    };
+    let (node, transform) = *node;
+    viewport.set_if_neq(TerminalViewport {
+        rect: displayed_rect(node, transform, window.scale_factor(), &context),
+        cells,
+    });
+    Ok(())
+}
+
+/// The area of the window where `node` shows the terminal of `context`, in logical pixels.
+pub(super) fn displayed_rect(
+    node: &ComputedNode,
+    transform: &UiGlobalTransform,
+    scale_factor: f32,
+    context: &WindowedContext,
+) -> Rect {
+    // UI nodes are laid out in physical pixels, centered on their translation.
+    let (scale, _, translation) = transform.to_scale_angle_translation();
+    let content = node.content_box();
+    let area = Rect::from_corners(
+        translation + content.min * scale,
+        translation + content.max * scale,
+    );
+    let area = Rect::from_corners(area.min / scale_factor, area.max / scale_factor);
+
+    let backend = context.backend();
+    let texture = Vec2::new(
+        backend.get_pixmap_width() as f32,
+        backend.get_pixmap_height() as f32,
+    );
+    letterbox(area, texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> TerminalViewport {
+        TerminalViewport {
+            rect: Rect::new(10.0, 20.0, 90.0, 50.0),
+            cells: Size::new(8, 3),
+        }
+    }
+
+    #[test]
+    fn positions_map_to_the_cell_containing_them() {
+        let viewport = viewport();
+        assert_eq!(
+            viewport.cell_at(Vec2::new(10.0, 20.0)),
+            Some(Position::new(0, 0))
+        );
+        assert_eq!(
+            viewport.cell_at(Vec2::new(25.0, 45.0)),
+            Some(Position::new(1, 2))
+        );
+        assert_eq!(viewport.cell_at(Vec2::new(9.0, 25.0)), None);
+        assert_eq!(viewport.cell_at(Vec2::new(90.0, 25.0)), None);
+    }
+
+    #[test]
+    fn cells_map_back_to_their_area() {
+        let viewport = viewport();
+        let rect = viewport.cell_rect(Position::new(1, 2));
+        assert_eq!(rect, Rect::new(20.0, 40.0, 30.0, 50.0));
+        assert_eq!(viewport.cell_at(rect.center()), Some(Position::new(1, 2)));
+
+        let area = viewport.area_rect(layout::Rect::new(2, 1, 3, 2));
+        assert_eq!(area, Rect::new(30.0, 30.0, 60.0, 50.0));
+    }
+
+    #[test]
+    fn uv_coordinates_map_to_cells() {
+        let viewport = viewport();
+        assert_eq!(viewport.cell_at_uv(Vec2::ZERO), Some(Position::new(0, 0)));
+        assert_eq!(
+            viewport.cell_at_uv(Vec2::new(0.5, 0.99)),
+            Some(Position::new(4, 2))
+        );
+        assert_eq!(viewport.cell_at_uv(Vec2::new(1.0, 0.5)), None);
+        assert_eq!(viewport.cell_at_uv(Vec2::new(0.5, -0.1)), None);
+    }
+
+    #[test]
+    fn letterboxing_keeps_the_aspect_ratio() {
+        let area = Rect::new(0.0, 0.0, 200.0, 100.0);
+        let rect = letterbox(area, Vec2::new(80.0, 80.0));
+        assert_eq!(rect, Rect::new(50.0, 0.0, 150.0, 100.0));
+    }
+}
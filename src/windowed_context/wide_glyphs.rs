@@ -0,0 +1,268 @@
+//! Drawing of double-width characters, and of the characters left out of glyph atlases, in
+//! windowed mode.
+//!
+//! The soft backend draws every character in a single cell, so double-width characters such as
+//! emoji and CJK would be cut in half. They are drawn again over both of their cells once the
+//! frame has been copied to the terminal's image. With outline fonts, the characters outside of
+//! the atlases are drawn the same way, over the blank cells the backend leaves for them, and only
+//! rasterized the first time they are shown.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::font::{CellMetrics, TerminalFont, in_atlas};
+
+/// The fonts double-width characters are drawn with, and the glyphs drawn so far.
+#[derive(Resource, Default)]
+pub(crate) struct WideGlyphs {
+    fonts: Vec<TerminalFont>,
+    size: f32,
+    cell: CellMetrics,
+    /// Whether the single-width characters outside of the atlases are drawn too.
+    narrow: bool,
+    /// The coverage of each character's glyph over its cells, or `None` if no font has it.
+    glyphs: HashMap<char, Option<Vec<u8>>>,
+}
+
+impl WideGlyphs {
+    /// Draws characters from the first of `fonts` that has them, at `size` pixels, and the
+    /// single-width characters outside of the atlases if `narrow`.
+    pub(super) fn new(
+        fonts: Vec<TerminalFont>,
+        size: f32,
+        cell: CellMetrics,
+        narrow: bool,
+    ) -> Self {
+        Self {
+            fonts,
+            size,
+            cell,
+            narrow,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// Draws the characters the backend can't in the given `rows` of `buffer` over the RGBA
+    /// `image` it was rendered to. The colors of `buffer` must have been resolved to the RGB values
+    /// they were drawn with.
+    pub(super) fn draw(
+        &mut self,
+        buffer: &Buffer,
+        image: &mut [u8],
+        image_width: usize,
+        rows: &[u16],
+    ) {
+        let (cell_width, cell_height) = (self.cell.width as usize, self.cell.height as usize);
+        let columns = buffer.area.width as usize;
+        let cells = rows.iter().flat_map(|&row| {
+            let start = row as usize * columns;
+            (start..start + columns).zip(&buffer.content[start..start + columns])
+        });
+        for (index, cell) in cells {
+            let Some(character) = cell.symbol().chars().next() else {
+                continue;
+            };
+            let columns = match cell.symbol().width() {
+                2 => 2,
+                1 if self.narrow && !in_atlas(character) => 1,
+                _ => continue,
+            };
+            let Color::Rgb(r, g, b) = cell.fg else {
+                continue;
+            };
+            if cell.modifier.contains(Modifier::HIDDEN) {
+                continue;
+            }
+            let box_width = cell_width * columns;
+            let glyph = self.glyphs.entry(character).or_insert_with(|| {
+                rasterize(&self.fonts, character, self.size, self.cell, columns)
+            });
+            let Some(glyph) = glyph else {
+                continue;
+            };
+
+            let (column, row) = buffer.pos_of(index);
+            let x0 = column as usize * cell_width;
+            let y0 = row as usize * cell_height;
+            // The backend has filled the cell with its background.
+            let pixel = |x: usize, y: usize| (y * image_width + x) * 4;
+            let Some(background) = image.get(pixel(x0, y0)..pixel(x0, y0) + 3) else {
+                continue;
+            };
+            let background = [background[0], background[1], background[2]];
+            let foreground = [r, g, b];
+
+            for y in 0..cell_height {
+                for x in 0..box_width.min(image_width.saturating_sub(x0)) {
+                    let Some(rgba) = image.get_mut(pixel(x0 + x, y0 + y)..) else {
+                        break;
+                    };
+                    let alpha = u16::from(glyph[y * box_width + x]);
+                    for channel in 0..3 {
+                        let blended = (u16::from(foreground[channel]) * alpha
+                            + u16::from(background[channel]) * (255 - alpha))
+                            / 255;
+                        rgba[channel] = blended as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The first font that has a glyph for `character` draws it across its `columns`, scaled
+/// down if it doesn't fit. Characters no font has are drawn with a replacement glyph.
+fn rasterize(
+    fonts: &[TerminalFont],
+    character: char,
+    size: f32,
+    cell: CellMetrics,
+    columns: usize,
+) -> Option<Vec<u8>> {
+    let (font, character) = [character, '\u{FFFD}', '?']
+        .into_iter()
+        .find_map(|character| {
+            let font = fonts.iter().find(|font| font.0.has_glyph(character))?;
+            Some((font, character))
+        })?;
+    let (box_width, box_height) = (cell.width * columns as u32, cell.height);
+    let metrics = font.0.metrics(character, size);
+    let scale = (box_width as f32 / metrics.width.max(1) as f32)
+        .min(box_height as f32 / metrics.height.max(1) as f32)
+        .min(1.0);
+    let (metrics, coverage) = font.0.rasterize(character, size * scale);
+    Some(place(&metrics, &coverage, cell, columns))
+}
+
+/// Places a rasterized glyph in the area of its `columns`: centered horizontally, on the
+/// baseline when it fits, and moved up or down to stay inside the cells when it doesn't.
+fn place(
+    metrics: &fontdue::Metrics,
+    coverage: &[u8],
+    cell: CellMetrics,
+    columns: usize,
+) -> Vec<u8> {
+    let (box_width, box_height) = (cell.width as i32 * columns as i32, cell.height as i32);
+    let (width, height) = (metrics.width as i32, metrics.height as i32);
+    let left = (box_width - width) / 2;
+    let top = (cell.baseline as i32 - metrics.ymin - height).clamp(0, (box_height - height).max(0));
+
+    let mut glyph = vec![0; (box_width * box_height) as usize];
+    for (i, &value) in coverage.iter().enumerate() {
+        let x = left + i as i32 % width;
+        let y = top + i as i32 / width;
+        if (0..box_width).contains(&x) && (0..box_height).contains(&y) {
+            glyph[(y * box_width + x) as usize] = value;
+        }
+    }
+    glyph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{attributes::resolve_colors, palette::TerminalPalette},
+        *,
+    };
+
+    const CELL: CellMetrics = CellMetrics {
+        width: 3,
+        height: 4,
+        baseline: 3,
+    };
+
+    fn metrics(ymin: i32, width: usize, height: usize) -> fontdue::Metrics {
+        fontdue::Metrics {
+            ymin,
+            width,
+            height,
+            ..default()
+        }
+    }
+
+    fn lit(glyph: &[u8]) -> Vec<(usize, usize)> {
+        let width = CELL.width as usize * 2;
+        (0..glyph.len())
+            .filter(|&i| glyph[i] != 0)
+            .map(|i| (i % width, i / width))
+            .collect()
+    }
+
+    #[test]
+    fn glyphs_are_centered_across_both_cells() {
+        let glyph = place(&metrics(0, 2, 1), &[255; 2], CELL, 2);
+        assert_eq!(lit(&glyph), [(2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn tall_glyphs_are_kept_inside_the_cells() {
+        let glyph = place(&metrics(-1, 1, 4), &[255; 4], CELL, 2);
+        assert_eq!(lit(&glyph), [(2, 0), (2, 1), (2, 2), (2, 3)]);
+        let glyph = place(&metrics(2, 1, 2), &[255; 2], CELL, 2);
+        assert_eq!(lit(&glyph), [(2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn double_width_characters_cover_two_cells() {
+        let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 3, 1));
+        buffer.set_string(
+            1,
+            0,
+            "日",
+            ratatui::style::Style::new().fg(ratatui::style::Color::Red),
+        );
+        let mut glyphs = WideGlyphs {
+            cell: CELL,
+            ..default()
+        };
+        let coverage = vec![255; (CELL.width * 2 * CELL.height) as usize];
+        glyphs.glyphs.insert('日', Some(coverage));
+
+        let width = CELL.width as usize * 3;
+        let mut image = vec![0; width * CELL.height as usize * 4];
+        let palette = TerminalPalette::default();
+        resolve_colors(&mut buffer, &palette);
+        glyphs.draw(&buffer, &mut image, width, &[0]);
+        let red: Vec<_> = image
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, rgba)| rgba[..3] == palette.colors[1])
+            .map(|(i, _)| i % width)
+            .collect();
+        assert_eq!(red.len(), 2 * (CELL.width * CELL.height) as usize);
+        assert!(red.iter().all(|&x| x >= CELL.width as usize));
+    }
+
+    #[test]
+    fn only_narrow_characters_outside_the_atlases_are_drawn() {
+        let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+        buffer.set_string(0, 0, "éж", ratatui::style::Style::new());
+        let mut glyphs = WideGlyphs {
+            cell: CELL,
+            narrow: true,
+            ..default()
+        };
+        let coverage = vec![255; (CELL.width * CELL.height) as usize];
+        glyphs.glyphs.insert('é', Some(coverage.clone()));
+        glyphs.glyphs.insert('ж', Some(coverage));
+
+        let width = CELL.width as usize * 2;
+        let mut image = vec![0; width * CELL.height as usize * 4];
+        let palette = TerminalPalette::default();
+        resolve_colors(&mut buffer, &palette);
+        glyphs.draw(&buffer, &mut image, width, &[0]);
+        let drawn: Vec<_> = image
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, rgba)| rgba[..3] != [0, 0, 0])
+            .map(|(i, _)| i % width)
+            .collect();
+        assert_eq!(drawn.len(), (CELL.width * CELL.height) as usize);
+        assert!(drawn.iter().all(|&x| x >= CELL.width as usize));
+    }
+}
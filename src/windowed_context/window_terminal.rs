@@ -0,0 +1,265 @@
+//! Terminals of their own in windows other than the primary one, in windowed mode.
+use bevy::{
+    camera::RenderTarget,
+    prelude::*,
+    ui::{ComputedNode, UiGlobalTransform},
+    window::WindowRef,
+};
+use ratatui::crossterm::event::Event;
+
+use crate::context::TerminalContext;
+
+use super::{
+    context::WindowedContext,
+    font::WindowedFont,
+    overlay::TextUnderlays,
+    palette::TerminalPalette,
+    plugin::{RenderResources, TerminalRenderer, terminal_image},
+    scaling::{WindowedScaling, fit_terminal_node, scalable_font},
+    viewport::{TerminalViewport, displayed_rect},
+};
+
+/// A terminal of its own in a window other than the primary one, e.g. for an inspector next to
+/// the main view.
+///
+/// Added to a [`Window`] entity, it is drawn to like [`RatatuiContext`][crate::RatatuiContext]
+/// and displayed in that window, fitted to it according to the [`WindowedScaling`], with the
+/// [`WindowedFont`] and [`TerminalPalette`] of the primary terminal. The keyboard, mouse, focus
+/// and resizes of the window are sent as [`WindowTerminalMessage`]s instead of the
+/// [input messages][crate::event], which keep reporting the other windows. The display is
+/// despawned with the window when it is closed.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_ratatui::windowed::WindowTerminal;
+/// fn open_inspector(mut commands: Commands) -> Result {
+///     let title = "Inspector".to_string();
+///     commands.spawn((Window { title, ..default() }, WindowTerminal::new()?));
+///     Ok(())
+/// }
+///
+/// fn draw_inspector(mut terminals: Query<&mut WindowTerminal>) -> Result {
+///     for mut terminal in &mut terminals {
+///         terminal.draw(|frame| frame.render_widget("Nothing selected", frame.area()))?;
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Component, Deref, DerefMut, Debug)]
+pub struct WindowTerminal {
+    #[deref]
+    context: WindowedContext,
+    image: Handle<Image>,
+    viewport: TerminalViewport,
+    display: Option<WindowDisplay>,
+    renderer: TerminalRenderer,
+}
+
+impl WindowTerminal {
+    /// A terminal for the window this is added to.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            context: WindowedContext::init()?,
+            image: Handle::default(),
+            viewport: TerminalViewport::default(),
+            display: None,
+            renderer: TerminalRenderer::default(),
+        })
+    }
+
+    /// The image the terminal is drawn to, once its window shows it.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// Where the terminal is displayed in its window, like the [`TerminalViewport`] of the primary
+    /// terminal.
+    pub fn viewport(&self) -> TerminalViewport {
+        self.viewport
+    }
+}
+
+/// The entities displaying a [`WindowTerminal`].
+#[derive(Debug, Clone, Copy)]
+struct WindowDisplay {
+    camera: Entity,
+    root: Entity,
+    node: Entity,
+}
+
+/// Input of a window with a [`WindowTerminal`], reported like a terminal would.
+///
+/// Keys are reported like [`KeyMessage`][crate::event::KeyMessage]s, the mouse like
+/// [`MouseMessage`][crate::event::MouseMessage]s, and a resize when the number of cells of the
+/// terminal changes.
+#[derive(Message, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WindowTerminalMessage {
+    /// The window the input happened in.
+    pub window: Entity,
+    /// The input.
+    pub event: Event,
+}
+
+/// System that spawns a camera and the UI nodes displaying each new [`WindowTerminal`] in its
+/// window.
+#[allow(clippy::type_complexity)]
+pub(super) fn spawn_window_terminal_displays(
+    mut commands: Commands,
+    mut terminals: Query<(Entity, &mut WindowTerminal), (Added<WindowTerminal>, With<Window>)>,
+    mut images: ResMut<Assets<Image>>,
+    scaling: Res<WindowedScaling>,
+    palette: Res<TerminalPalette>,
+) {
+    for (window, mut terminal) in &mut terminals {
+        let image = images.add(terminal_image());
+        let camera = commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    clear_color: ClearColorConfig::Custom(scaling.background),
+                    ..default()
+                },
+                RenderTarget::Window(WindowRef::Entity(window)),
+            ))
+            .id();
+        // Sized by `fit_window_terminals`.
+        let node = commands
+            .spawn((
+                ImageNode::new(image.clone()).with_mode(NodeImageMode::Stretch),
+                Node::default(),
+            ))
+            .id();
+        let root = commands
+            .spawn((
+                UiTargetCamera(camera),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ))
+            .add_child(node)
+            .id();
+        terminal.image = image;
+        terminal.display = Some(WindowDisplay { camera, root, node });
+        terminal.set_palette(palette.clone());
+    }
+}
+
+/// Observer that despawns the display of a [`WindowTerminal`] removed from its window, e.g. when
+/// the window is closed.
+pub(super) fn despawn_window_terminal_display(
+    remove: On<Remove, WindowTerminal>,
+    terminals: Query<&WindowTerminal>,
+    mut commands: Commands,
+) {
+    let Some(display) = terminals
+        .get(remove.entity)
+        .ok()
+        .and_then(|terminal| terminal.display)
+    else {
+        return;
+    };
+    commands.entity(display.camera).try_despawn();
+    commands.entity(display.root).try_despawn();
+}
+
+/// System that fits the grid of each [`WindowTerminal`] to its window, sending a
+/// [`WindowTerminalMessage`] when its number of cells changes.
+pub(super) fn resize_window_terminals(
+    mut terminals: Query<(Entity, &Window, &mut WindowTerminal)>,
+    scaling: Res<WindowedScaling>,
+    font: Option<Res<WindowedFont>>,
+    mut messages: MessageWriter<WindowTerminalMessage>,
+) -> Result {
+    for (window_entity, window, mut terminal) in &mut terminals {
+        let backend = terminal.backend();
+        let char_size = UVec2::new(backend.char_width as u32, backend.char_height as u32);
+        let cells = scaling
+            .mode
+            .grid_size(window, scalable_font(font.as_deref()), char_size);
+        if terminal.size()? == cells {
+            continue;
+        }
+        terminal.backend_mut().resize(cells.width, cells.height);
+        messages.write(WindowTerminalMessage {
+            window: window_entity,
+            event: Event::Resize(cells.width, cells.height),
+        });
+    }
+    Ok(())
+}
+
+/// System that keeps the viewport of each [`WindowTerminal`] up to date with its node.
+pub(super) fn update_window_terminal_viewports(
+    mut terminals: Query<(&Window, &mut WindowTerminal)>,
+    nodes: Query<(&ComputedNode, &UiGlobalTransform)>,
+) -> Result {
+    for (window, mut terminal) in &mut terminals {
+        let cells = terminal.size()?;
+        let rect = terminal
+            .display
+            .and_then(|display| nodes.get(display.node).ok())
+            .map(|(node, transform)| {
+                displayed_rect(node, transform, window.scale_factor(), &terminal.context)
+            })
+            .unwrap_or_default();
+        let viewport = TerminalViewport { rect, cells };
+        if terminal.viewport != viewport {
+            terminal.viewport = viewport;
+        }
+    }
+    Ok(())
+}
+
+/// System that sizes the node of each [`WindowTerminal`] to the [`WindowedScaling`].
+pub(super) fn fit_window_terminals(
+    terminals: Query<(&Window, &WindowTerminal)>,
+    mut nodes: Query<(&ImageNode, &mut Node)>,
+    mut cameras: Query<&mut Camera>,
+    scaling: Res<WindowedScaling>,
+    font: Option<Res<WindowedFont>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for (window, terminal) in &terminals {
+        let Some(display) = terminal.display else {
+            continue;
+        };
+        let (Ok((image_node, mut node)), Ok(mut camera)) =
+            (nodes.get_mut(display.node), cameras.get_mut(display.camera))
+        else {
+            continue;
+        };
+        fit_terminal_node(
+            &scaling,
+            scalable_font(font.as_deref()),
+            &terminal.context,
+            window,
+            image_node,
+            &mut node,
+            &mut camera,
+            &mut images,
+        );
+    }
+}
+
+/// System that copies each [`WindowTerminal`] to its image.
+pub(super) fn render_window_terminals(
+    mut terminals: Query<&mut WindowTerminal>,
+    mut resources: RenderResources,
+) {
+    // Wide characters are drawn with other fonts.
+    let invalidate = resources.wide_glyphs.is_changed();
+    for mut terminal in &mut terminals {
+        let terminal = &mut *terminal;
+        terminal.renderer.render(
+            &terminal.context,
+            &terminal.image,
+            &TextUnderlays::default(),
+            invalidate,
+            &mut resources,
+        );
+    }
+}